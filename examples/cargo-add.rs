@@ -21,7 +21,7 @@ fn run() -> Result<(), Box<Error>> {
 
 
     let new_dep = key_value("parsehole = \"6.2.8\"")?;
-    manifest["dependencies"].append(new_dep.0, new_dep.1)?;
+    manifest["dependencies"].as_table_mut()?.insert(new_dep.0, new_dep.1)?;
 
     let mut out = File::create("examples/cargo_new.toml")?;
     out.write(manifest.as_string().as_bytes())?;
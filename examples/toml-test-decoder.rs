@@ -0,0 +1,34 @@
+//! The decoder for the toml-test suite: reads a TOML document on stdin, and
+//! writes its values as tagged JSON on stdout.
+//!
+//! The parser only reads offset datetimes: the cases of the suite with local
+//! datetimes, dates or times fail.
+//!
+//! ```text
+//! cargo build --example toml-test-decoder --features json
+//! toml-test target/debug/examples/toml-test-decoder
+//! ```
+
+extern crate Molten;
+
+use std::error::Error;
+use std::io::{self, Read};
+use std::process;
+
+use Molten::json::to_tagged_json;
+use Molten::parser::Parser;
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut src = String::new();
+    io::stdin().read_to_string(&mut src)?;
+    let doc = Parser::new(&src).parse()?;
+    println!("{}", to_tagged_json(&doc)?);
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! The encoder for the toml-test suite: reads tagged JSON on stdin, and
+//! writes the document built from it on stdout.
+//!
+//! The `datetime-local`, `date-local` and `time-local` types are rejected as
+//! unsupported, since documents only hold offset datetimes: the cases of the
+//! suite that use them fail.
+//!
+//! ```text
+//! cargo build --example toml-test-encoder --features json
+//! toml-test -encoder target/debug/examples/toml-test-encoder
+//! ```
+
+extern crate Molten;
+
+use std::error::Error;
+use std::io::{self, Read};
+use std::process;
+
+use Molten::json::from_tagged_json;
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut src = String::new();
+    io::stdin().read_to_string(&mut src)?;
+    let mut buf = String::new();
+    let doc = from_tagged_json(&src, &mut buf)?;
+    print!("{}", doc.as_string());
+    Ok(())
+}
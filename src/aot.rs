@@ -0,0 +1,255 @@
+//! Editing and iteration of arrays of tables.
+//!
+//! The blank lines that separate two `[[name]]` entries are stored at the
+//! end of the body of the first one, so entries are moved around together
+//! with that spacing: the last entry keeps whatever separated the array
+//! from the rest of the document, and the others keep the spacing used
+//! between siblings.
+
+use items::*;
+use errors::*;
+use container::Container;
+
+/// Returns the container at the very end of a table's text: the body of
+/// its last child table if it has one, or its own body otherwise.
+pub(crate) fn tail<'b, 'a>(c: &'b Container<'a>) -> &'b Container<'a> {
+    match c.body.last() {
+        Some(&(_, Item::Table { ref val, .. })) => tail(val),
+        Some(&(_, Item::AoT(ref tables))) if !tables.is_empty() => {
+            tail(body(tables.last().unwrap()))
+        }
+        _ => c,
+    }
+}
+
+/// Mutable version of `tail`.
+pub(crate) fn tail_mut<'b, 'a>(c: &'b mut Container<'a>) -> &'b mut Container<'a> {
+    let descend = match c.body.last() {
+        Some(&(_, Item::Table { .. })) => true,
+        Some(&(_, Item::AoT(ref tables))) => !tables.is_empty(),
+        _ => false,
+    };
+    if !descend {
+        return c;
+    }
+    match c.body.last_mut() {
+        Some(&mut (_, Item::Table { ref mut val, .. })) => tail_mut(val),
+        Some(&mut (_, Item::AoT(ref mut tables))) => tail_mut(body_mut(tables.last_mut().unwrap())),
+        _ => unreachable!(),
+    }
+}
+
+/// Returns the body of an entry.
+fn body<'b, 'a>(table: &'b Item<'a>) -> &'b Container<'a> {
+    match *table {
+        Item::Table { ref val, .. } => val,
+        _ => unreachable!(),
+    }
+}
+
+/// Mutable version of `body`.
+fn body_mut<'b, 'a>(table: &'b mut Item<'a>) -> &'b mut Container<'a> {
+    match *table {
+        Item::Table { ref mut val, .. } => val,
+        _ => unreachable!(),
+    }
+}
+
+/// Removes and returns the whitespace at the end of an entry.
+fn take_trailing_ws<'a>(table: &mut Item<'a>) -> Vec<&'a str> {
+    let tail = tail_mut(body_mut(table));
+    let mut ws = Vec::new();
+    while let Some(&(None, Item::WS(s))) = tail.body.last() {
+        ws.insert(0, s);
+        tail.body.pop();
+    }
+    ws
+}
+
+/// Appends whitespace at the end of an entry.
+fn put_trailing_ws<'a>(table: &mut Item<'a>, ws: &[&'a str]) {
+    let tail = tail_mut(body_mut(table));
+    for s in ws {
+        tail.body.push((None, Item::WS(s)));
+    }
+}
+
+/// Returns the whitespace that separates two entries, as found after
+/// the one before last, or after the only entry.
+fn separator<'a>(tables: &[Item<'a>]) -> Vec<&'a str> {
+    let idx = tables.len().saturating_sub(2);
+    let tail = tail(body(&tables[idx]));
+    let start = tail.body
+        .iter()
+        .rposition(|entry| entry.0.is_some() || !entry.1.is_ws())
+        .map_or(0, |i| i + 1);
+    tail.body[start..]
+        .iter()
+        .map(|entry| match entry.1 {
+            Item::WS(s) => s,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Inserts `table` so that it becomes the entry at index `idx`.
+pub(crate) fn insert<'a>(tables: &mut Vec<Item<'a>>, idx: usize, mut table: Item<'a>) -> Result<()> {
+    match table {
+        Item::Table { ref mut is_aot_elem, .. } => *is_aot_elem = true,
+        _ => bail!(ErrorKind::APIWrongItem),
+    }
+    if idx > tables.len() {
+        bail!(ErrorKind::IndexOutOfBounds(idx));
+    }
+    if tables.is_empty() {
+        tables.push(table);
+        return Ok(());
+    }
+
+    let sep = separator(tables);
+    let sibling = if idx < tables.len() { idx } else { idx - 1 };
+    table.trivia_mut().indent = tables[sibling].trivia().indent;
+
+    if idx == tables.len() {
+        // The new entry ends the array and takes over what followed it.
+        let last = tables.len() - 1;
+        let tail = take_trailing_ws(&mut tables[last]);
+        put_trailing_ws(&mut tables[last], &sep);
+        put_trailing_ws(&mut table, &tail);
+    } else {
+        put_trailing_ws(&mut table, &sep);
+    }
+    tables.insert(idx, table);
+    Ok(())
+}
+
+/// Removes the entry at index `idx` and returns it.
+pub(crate) fn remove<'a>(tables: &mut Vec<Item<'a>>, idx: usize) -> Result<Item<'a>> {
+    if idx >= tables.len() {
+        bail!(ErrorKind::IndexOutOfBounds(idx));
+    }
+    let mut table = tables.remove(idx);
+    if idx == tables.len() && idx > 0 {
+        // The entry before takes over what followed the array.
+        let tail = take_trailing_ws(&mut table);
+        let _ = take_trailing_ws(&mut tables[idx - 1]);
+        put_trailing_ws(&mut tables[idx - 1], &tail);
+    }
+    Ok(table)
+}
+
+/// An iterator over the bodies of the entries of an array of tables.
+#[derive(Debug)]
+pub struct AoTIterator<'b, 'a: 'b> {
+    pub(crate) tables: ::std::slice::Iter<'b, Item<'a>>,
+}
+
+impl<'b, 'a: 'b> Iterator for AoTIterator<'b, 'a> {
+    type Item = &'b Container<'a>;
+
+    fn next(&mut self) -> Option<&'b Container<'a>> {
+        self.tables.next().map(|table| match *table {
+            Item::Table { ref val, .. } => val,
+            _ => unreachable!(),
+        })
+    }
+}
+
+/// A mutable iterator over the bodies of the entries of an array of tables.
+#[derive(Debug)]
+pub struct AoTIteratorMut<'b, 'a: 'b> {
+    pub(crate) tables: ::std::slice::IterMut<'b, Item<'a>>,
+}
+
+impl<'b, 'a: 'b> Iterator for AoTIteratorMut<'b, 'a> {
+    type Item = &'b mut Container<'a>;
+
+    fn next(&mut self) -> Option<&'b mut Container<'a>> {
+        self.tables.next().map(body_mut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+    use items::*;
+    use api::*;
+
+    fn entry(kv: &'static str) -> Item<'static> {
+        let mut t = table().unwrap();
+        let (k, v) = key_value(kv).unwrap();
+        t.append(k, v).unwrap();
+        t
+    }
+
+    #[test]
+    fn push() {
+        let src = "[[bin]]\nname = \"a\"\n\n[[bin]]\nname = \"b\"\n\n[dependencies]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc["bin"].push(entry("name = \"c\"\n")).unwrap();
+        let expected = format!(
+            "[[bin]]\nname = \"a\"\n\n[[bin]]\nname = \"b\"\n\n[[bin]]{}name = \"c\"\n\n[dependencies]\n",
+            ::NL
+        );
+        assert_eq!(doc.as_string(), expected);
+        Parser::new(&doc.as_string()).parse().unwrap();
+    }
+
+    #[test]
+    fn push_indented() {
+        let src = "[[a]]\nx = 1\n    [[a.b]]\n    y = 1\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        {
+            let a = &mut doc["a"];
+            let b = a.tables_mut().unwrap().next().unwrap();
+            b["a.b"].push(table().unwrap()).unwrap();
+        }
+        let expected = format!("[[a]]\nx = 1\n    [[a.b]]\n    y = 1\n    [[a.b]]{}", ::NL);
+        assert_eq!(doc.as_string(), expected);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let src = "[[t]]\nid = 1\n\n[[t]]\nid = 2\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let mut first = table().unwrap();
+        first.append(Key::new("id"), integer("0").unwrap()).unwrap();
+        doc["t"].insert(0, first).unwrap();
+        let expected = format!("[[t]]{nl}id = 0{nl}\n[[t]]\nid = 1\n\n[[t]]\nid = 2\n", nl = ::NL);
+        assert_eq!(doc.as_string(), expected);
+
+        let removed = doc["t"].remove_at(2).unwrap();
+        assert_eq!(removed["id"].as_string(), "2");
+        let expected = format!("[[t]]{nl}id = 0{nl}\n[[t]]\nid = 1\n", nl = ::NL);
+        assert_eq!(doc.as_string(), expected);
+
+        assert!(doc["t"].remove_at(2).is_err());
+        assert!(doc["t"].insert(0, integer("1").unwrap()).is_err());
+    }
+
+    #[test]
+    fn iterate() {
+        let src = "[[t]]\nid = 1\n[[t]]\nid = 2\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let ids: Vec<_> = doc["t"].tables().unwrap().map(|t| t["id"].as_string()).collect();
+        assert_eq!(ids, vec!["1", "2"]);
+        for t in doc["t"].tables_mut().unwrap() {
+            t.append(Key::new("ok"), bool("true").unwrap()).unwrap();
+        }
+        let expected = format!("[[t]]\nid = 1\nok = true{nl}[[t]]\nid = 2\nok = true{nl}", nl = ::NL);
+        assert_eq!(doc.as_string(), expected);
+    }
+
+    #[test]
+    fn container_append() {
+        let src = "[[t]]\nid = 1\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.append(Key::new("t"), table().unwrap()).unwrap_err();
+        let mut entry = table().unwrap();
+        if let Item::Table { ref mut is_aot_elem, .. } = entry {
+            *is_aot_elem = true;
+        }
+        doc.append(Key::new("t"), entry).unwrap();
+        assert_eq!(doc.as_string(), format!("[[t]]\nid = 1\n[[t]]{}", ::NL));
+    }
+}
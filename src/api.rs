@@ -1,8 +1,11 @@
 //! Molten Public API
 
+use std::borrow::Cow;
+
 use items::*;
 use container::*;
 use errors::*;
+use aot::{AoTIterator, AoTIteratorMut};
 
 use chrono::DateTime as ChronoDateTime;
 
@@ -111,26 +114,206 @@ impl<'a> Item<'a> {
     /// Append a (key, value) to the current table.
     pub fn append<K: Into<Option<Key<'a>>>>(&mut self, key: K, item: Item<'a>) -> Result<()> {
         use Item::*;
+        let key = key.into();
         match *self {
             Table { ref mut val, .. } |
             InlineTable { ref mut val, .. } => val.append(key, item),
-            Array { .. } | AoT { .. } => unimplemented!(),
+            Array { .. } | AoT { .. } if key.is_none() => self.push(item),
             _ => bail!(ErrorKind::APIWrongItem),
         }
     }
 
-    /// Remove the (key, value) `key` from the current table.
-    pub fn remove(&mut self, key: &Key<'a>) -> Result<()> {
+    /// Remove the (key, value) `key` from the current table and return it.
+    pub fn remove(&mut self, key: &Key<'a>) -> Result<(Key<'a>, Item<'a>)> {
         use Item::*;
         match *self {
             Table { ref mut val, .. } |
             InlineTable { ref mut val, .. } => val.remove(key),
-            Array { .. } | AoT { .. } => unimplemented!(),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Rename the entry `old` of the current table to `new`.
+    pub fn rename_key<K: Into<Cow<'a, str>>>(&mut self, old: &Key<'a>, new: K) -> Result<()> {
+        use Item::*;
+        match *self {
+            Table { ref mut val, .. } |
+            InlineTable { ref mut val, .. } => val.rename_key(old, new),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+}
+
+/// Table access
+impl<'a> Item<'a> {
+    /// Returns the container of the current table or inline table.
+    pub fn as_table(&self) -> Result<&Container<'a>> {
+        match *self {
+            Item::Table { ref val, .. } | Item::InlineTable { ref val, .. } => Ok(val),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Returns the mutable container of the current table or inline table.
+    pub fn as_table_mut(&mut self) -> Result<&mut Container<'a>> {
+        match *self {
+            Item::Table { ref mut val, .. } | Item::InlineTable { ref mut val, .. } => Ok(val),
             _ => bail!(ErrorKind::APIWrongItem),
         }
     }
 }
 
+/// String access
+impl<'a> Item<'a> {
+    /// Returns the value of the current string, with its escapes decoded and
+    /// the newline right after the opening quotes of a multi-line string
+    /// trimmed.
+    pub fn as_str(&self) -> Result<Cow<'a, str>> {
+        match *self {
+            Item::Str { ref t, val, .. } => Ok(::items::decode_string(t, val)),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+}
+
+/// Array and AoT editing
+///
+/// New elements follow the layout of the existing ones: a single-line array
+/// stays on one line, and a multi-line array gets one element per line with
+/// the same indentation and trailing comma. New `[[name]]` entries of an
+/// AoT take the indentation and blank-line spacing of their siblings.
+impl<'a> Item<'a> {
+    /// Append a value to the end of the current array, or a table to the
+    /// end of the current AoT.
+    pub fn push(&mut self, item: Item<'a>) -> Result<()> {
+        match *self {
+            Item::Array { ref mut val, .. } => {
+                let len = ::array::len(val);
+                ::array::insert(val, len, item)
+            }
+            Item::AoT(ref mut tables) => {
+                let len = tables.len();
+                ::aot::insert(tables, len, item)
+            }
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Insert a value at index `idx` of the current array, or a table at
+    /// index `idx` of the current AoT, shifting the following elements to
+    /// the right.
+    pub fn insert(&mut self, idx: usize, item: Item<'a>) -> Result<()> {
+        match *self {
+            Item::Array { ref mut val, .. } => ::array::insert(val, idx, item),
+            Item::AoT(ref mut tables) => ::aot::insert(tables, idx, item),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Remove and return the element at index `idx` of the current array
+    /// or AoT.
+    pub fn remove_at(&mut self, idx: usize) -> Result<Item<'a>> {
+        match *self {
+            Item::Array { ref mut val, .. } => ::array::remove(val, idx),
+            Item::AoT(ref mut tables) => ::aot::remove(tables, idx),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Keep only the values of the current array for which `f` returns `true`.
+    pub fn retain<F>(&mut self, f: F) -> Result<()>
+    where
+        F: FnMut(&Item<'a>) -> bool,
+    {
+        match *self {
+            Item::Array { ref mut val, .. } => {
+                ::array::retain(val, f);
+                Ok(())
+            }
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Remove all values, comments and whitespace from the current array.
+    pub fn clear(&mut self) -> Result<()> {
+        match *self {
+            Item::Array { ref mut val, .. } => {
+                val.clear();
+                Ok(())
+            }
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Returns an iterator over the bodies of the tables in the current AoT.
+    pub fn tables(&self) -> Result<AoTIterator<'_, 'a>> {
+        match *self {
+            Item::AoT(ref tables) => Ok(AoTIterator { tables: tables.iter() }),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Returns a mutable iterator over the bodies of the tables in the
+    /// current AoT.
+    pub fn tables_mut(&mut self) -> Result<AoTIteratorMut<'_, 'a>> {
+        match *self {
+            Item::AoT(ref mut tables) => Ok(AoTIteratorMut { tables: tables.iter_mut() }),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+}
+
+/// Value replacement
+impl<'a> Item<'a> {
+    /// Replaces the current value with `value` and returns the old one.
+    ///
+    /// Only the value itself is swapped: the indentation, trailing comment
+    /// and newline of the current value are kept. A string is written with
+    /// the same quotes as the old one if its text reads the same inside
+    /// them. Integers are always decimal in this crate, so there is no
+    /// radix to carry over.
+    pub fn set_value(&mut self, mut value: Item<'a>) -> Result<Item<'a>> {
+        if !is_inline_value(self) || !is_inline_value(&value) {
+            bail!(ErrorKind::APIWrongItem);
+        }
+        *value.trivia_mut() = self.trivia().clone();
+        if let (Item::Str { t: old, .. }, Item::Str { t, original, .. }) = (&*self, &mut value) {
+            if fits(original, t, old) {
+                *t = old.clone();
+            }
+        }
+        Ok(::std::mem::replace(self, value))
+    }
+}
+
+/// Returns true if `item` is a value that can appear after `key = `.
+fn is_inline_value(item: &Item) -> bool {
+    matches!(
+        *item,
+        Item::Integer { .. } | Item::Float { .. } | Item::Bool { .. } | Item::DateTime { .. } |
+        Item::Array { .. } | Item::InlineTable { .. } | Item::Str { .. }
+    )
+}
+
+/// Returns true if the text of a string written as `from` reads the same
+/// when written as `to`.
+fn fits(original: &str, from: &StringType, to: &StringType) -> bool {
+    let literal = |t: &StringType| *t == StringType::SLL || *t == StringType::MLL;
+    let multiline = |t: &StringType| *t == StringType::MLB || *t == StringType::MLL;
+
+    if literal(from) != literal(to) && original.contains('\\') {
+        return false;
+    }
+    if multiline(to) {
+        // The newline right after the opening delimiter is trimmed.
+        !original.starts_with('\n') && !original.starts_with("\r\n") &&
+            !original.contains(to.delimiter()) &&
+            !original.ends_with(&to.delimiter()[..1])
+    } else {
+        !original.contains('\n') && !original.contains(to.delimiter())
+    }
+}
+
 /// Identity methods
 impl<'a> Item<'a> {
     /// Returns true if Item is a value.
@@ -316,7 +499,13 @@ mod tests {
         assert!(i.is_ok());
         let mut item = i.unwrap();
         assert!(item.is_array());
-        // TODO(markcol): add tests for append/remove
+        assert!(item.append(None, integer("1").unwrap()).is_ok());
+        assert!(item.push(integer("2").unwrap()).is_ok());
+        assert!(item.append(Key::new("Key1"), integer("3").unwrap()).is_err());
+        assert_eq!(item.as_string(), "[1, 2]");
+        assert!(item.remove_at(0).unwrap().is_integer());
+        assert_eq!(item.as_string(), "[2]");
+        assert!(item.remove(&Key::new("Key1")).is_err());
     }
 
     #[allow(unused_mut)]
@@ -331,7 +520,7 @@ mod tests {
         assert!(item.append(Key::new(key), string(s).unwrap()).is_ok());
         assert_eq!(&item[key].as_string(), s);
         assert!(item.remove(&Key::new(key)).is_ok());
-        assert_eq!(&item[key].as_string(), "");
+        assert!(item.remove(&Key::new(key)).is_err());
     }
 
     #[test]
@@ -345,7 +534,25 @@ mod tests {
         assert!(item.append(Key::new(key), string(s).unwrap()).is_ok());
         assert_eq!(&item[key].as_string(), s);
         assert!(item.remove(&Key::new(key)).is_ok());
-        assert_eq!(&item[key].as_string(), "");
+        assert!(item.remove(&Key::new(key)).is_err());
+    }
+
+    #[test]
+    fn api_set_value() {
+        let mut item = string("'old'").unwrap();
+        item.trivia_mut().comment = "# note";
+        assert!(item.set_value(string("\"new\"").unwrap()).is_ok());
+        assert_eq!(item.as_string(), "'new'");
+        assert_eq!(item.trivia().comment, "# note");
+        // The text would read differently in a literal string.
+        assert!(item.set_value(string("\"a\\tb\"").unwrap()).is_ok());
+        assert_eq!(item.as_string(), "\"a\\tb\"");
+        assert!(item.set_value(string("'''it's'''").unwrap()).is_ok());
+        assert_eq!(item.as_string(), "\"it's\"");
+        assert!(item.set_value(integer("1").unwrap()).is_ok());
+        assert!(item.is_integer());
+        assert!(item.set_value(table().unwrap()).is_err());
+        assert!(item.set_value(aot().unwrap()).is_err());
     }
 
     #[test]
@@ -0,0 +1,370 @@
+//! Layout-preserving editing of array contents.
+//!
+//! The elements of an `Item::Array` are stored interleaved with the
+//! whitespace (commas included) and comments that surround them. The
+//! functions in this module edit that sequence so that the result keeps the
+//! look of the original array: new elements reuse the existing separators
+//! and indentation, and removed elements take their separator and same-line
+//! comment with them.
+
+use items::*;
+use errors::*;
+
+/// Returns the positions of the values in `val`, skipping trivia.
+fn positions(val: &[Item]) -> Vec<usize> {
+    val.iter()
+        .enumerate()
+        .filter(|&(_, it)| it.is_value())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns the number of values in the array.
+pub(crate) fn len(val: &[Item]) -> usize {
+    val.iter().filter(|it| it.is_value()).count()
+}
+
+/// Returns `true` if the array spans several lines.
+fn is_multiline(val: &[Item]) -> bool {
+    val.iter().any(|it| match *it {
+        Item::WS(s) => s.contains('\n'),
+        Item::Comment(_) => true,
+        _ => false,
+    })
+}
+
+/// Returns the indentation of the value at position `p`, or an empty string
+/// if it doesn't start a line.
+fn indent_of<'a>(val: &[Item<'a>], p: usize) -> &'a str {
+    if p == 0 {
+        return "";
+    }
+    match val[p - 1] {
+        Item::WS(s) => match s.rfind('\n') {
+            Some(i) => &s[i + 1..],
+            None if p > 1 && val[p - 2].is_comment() => s,
+            None => "",
+        },
+        _ => "",
+    }
+}
+
+/// Returns the trivia to place between two consecutive values.
+///
+/// The last whitespace-only gap found between two values is reused as is.
+/// Failing that, the separator is derived from the layout of the array.
+fn separator<'a>(val: &[Item<'a>], pos: &[usize]) -> Vec<Item<'a>> {
+    for w in pos.windows(2).rev() {
+        if w[1] == w[0] + 2 {
+            if let Item::WS(s) = val[w[0] + 1] {
+                return vec![Item::WS(s)];
+            }
+        }
+    }
+    if is_multiline(val) {
+        let mut sep = vec![Item::WS(","), Item::WS(::NL)];
+        let indent = pos.first().map(|&p| indent_of(val, p)).unwrap_or("");
+        if !indent.is_empty() {
+            sep.push(Item::WS(indent));
+        }
+        sep
+    } else {
+        vec![Item::WS(", ")]
+    }
+}
+
+/// Checks that `item` can be stored in the array.
+fn check<'a>(val: &[Item<'a>], item: &Item<'a>) -> Result<()> {
+    match *item {
+        Item::WS(_) | Item::Comment(_) | Item::Table { .. } | Item::AoT(_) | Item::None => {
+            bail!(ErrorKind::APIWrongItem)
+        }
+        _ => {}
+    }
+    if let Some(first) = val.iter().find(|it| it.is_value()) {
+        if first.discriminant() != item.discriminant() {
+            bail!(ErrorKind::MixedArrayTypes);
+        }
+    }
+    Ok(())
+}
+
+/// Inserts `item` so that it becomes the value at index `idx`.
+pub(crate) fn insert<'a>(val: &mut Vec<Item<'a>>, idx: usize, mut item: Item<'a>) -> Result<()> {
+    check(val, &item)?;
+    let pos = positions(val);
+    if idx > pos.len() {
+        bail!(ErrorKind::IndexOutOfBounds(idx));
+    }
+    // Elements carry no trivia of their own inside an array.
+    *item.trivia_mut() = Trivia::default();
+
+    if pos.is_empty() {
+        if val.iter().all(|it| it.is_ws()) {
+            val.clear();
+        }
+        val.push(item);
+        return Ok(());
+    }
+
+    let sep = separator(val, &pos);
+    if idx < pos.len() {
+        // Take the place of the current value, which moves after the separator.
+        let p = pos[idx];
+        let _ = val.splice(p..p, Some(item).into_iter().chain(sep));
+        return Ok(());
+    }
+
+    // Appending: a comment on the same line as the last value stays with it.
+    let last = pos[pos.len() - 1];
+    let mut j = last + 1;
+    while let Some(&Item::WS(s)) = val.get(j) {
+        if s.contains('\n') {
+            break;
+        }
+        j += 1;
+    }
+    if let Some(&Item::Comment(_)) = val.get(j) {
+        let has_comma = val[last + 1..j].iter().any(|it| match *it {
+            Item::WS(s) => s.contains(','),
+            _ => false,
+        });
+        let indent = indent_of(val, last);
+        let mut new = vec![Item::WS(indent), item, Item::WS(::NL)];
+        new.retain(|it| *it != Item::WS(""));
+        let _ = val.splice(j + 1..j + 1, new);
+        if !has_comma {
+            val.insert(last + 1, Item::WS(","));
+        }
+    } else {
+        let at = last + 1;
+        let _ = val.splice(at..at, sep.into_iter().chain(Some(item)));
+    }
+    Ok(())
+}
+
+/// Removes the value at index `idx` and returns it.
+pub(crate) fn remove<'a>(val: &mut Vec<Item<'a>>, idx: usize) -> Result<Item<'a>> {
+    let pos = positions(val);
+    if idx >= pos.len() {
+        bail!(ErrorKind::IndexOutOfBounds(idx));
+    }
+    let p = pos[idx];
+
+    if pos.len() == 1 {
+        let item = val.remove(p);
+        if val.iter().all(|it| it.is_ws()) {
+            val.clear();
+        }
+        return Ok(item);
+    }
+
+    if idx + 1 < pos.len() {
+        // Take the separator that follows the value, up to the end of its
+        // line, along with any comment sharing that line.
+        let next = pos[idx + 1];
+        let mut end = p + 1;
+        while end < next {
+            match val[end] {
+                Item::WS(s) => {
+                    end += 1;
+                    if s.contains('\n') {
+                        break;
+                    }
+                }
+                Item::Comment(_) => {
+                    end += 1;
+                    // The comment ended the line; drop the indentation that
+                    // follows since the value's own is kept.
+                    if end < next {
+                        if let Item::WS(s) = val[end] {
+                            if !s.contains('\n') {
+                                end += 1;
+                            }
+                        }
+                    }
+                    break;
+                }
+                _ => break,
+            }
+        }
+        let item = val.drain(p..end).next().unwrap();
+        return Ok(item);
+    }
+
+    // Last value: take the separator that precedes it instead, stopping at
+    // any comment that belongs to the previous value.
+    let prev = pos[idx - 1];
+    let mut start = p;
+    while start > prev + 1 && val[start - 1].is_ws() {
+        start -= 1;
+    }
+    let kept_comment = start > prev + 1;
+
+    // A comment on the value's line goes with it, but its line break stays.
+    let mut end = p + 1;
+    let mut tail = Vec::new();
+    {
+        let mut j = end;
+        while let Some(&Item::WS(s)) = val.get(j) {
+            if s.contains('\n') {
+                break;
+            }
+            j += 1;
+        }
+        if let Some(&Item::Comment(Trivia { trail, .. })) = val.get(j) {
+            // The comma that followed the value now follows the previous one.
+            let comma = val[end..j].iter().any(|it| matches!(*it, Item::WS(s) if s.contains(',')));
+            if comma && !kept_comment {
+                tail.push(Item::WS(","));
+            }
+            end = j + 1;
+            tail.push(Item::WS(trail));
+        }
+    }
+    if kept_comment && tail.is_empty() {
+        // The previous line already ends with a comment, and with the comma
+        // that preceded it: what remains of the value's line must go.
+        if let Some(&Item::WS(s)) = val.get(end) {
+            end += 1;
+            if let Some(i) = s.find('\n') {
+                tail.push(Item::WS(&s[i + 1..]));
+            }
+        }
+    }
+    tail.retain(|it| *it != Item::WS(""));
+
+    let item = val.splice(start..end, tail)
+        .find(|it| it.is_value())
+        .unwrap();
+    Ok(item)
+}
+
+/// Removes all the values for which `f` returns `false`.
+pub(crate) fn retain<'a, F>(val: &mut Vec<Item<'a>>, mut f: F)
+where
+    F: FnMut(&Item<'a>) -> bool,
+{
+    let drop: Vec<usize> = val.iter()
+        .filter(|it| it.is_value())
+        .enumerate()
+        .filter(|&(_, it)| !f(it))
+        .map(|(i, _)| i)
+        .collect();
+    for idx in drop.into_iter().rev() {
+        let _ = remove(val, idx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+    use items::*;
+    use api::*;
+
+    /// Parses `src` as a key/value, applies `edit` to the array and returns
+    /// the resulting array text, after checking that it is still valid.
+    fn edit<F: Fn(&mut Item)>(src: &str, edit: F) -> String {
+        let mut doc = Parser::new(src).parse().unwrap();
+        edit(&mut doc["a"]);
+        let out = doc.as_string();
+        Parser::new(&out).parse().unwrap();
+        out
+    }
+
+    #[test]
+    fn push_single_line() {
+        let out = edit("a = [1, 2]", |a| a.push(integer("3").unwrap()).unwrap());
+        assert_eq!(out, "a = [1, 2, 3]");
+        let out = edit("a = [ 1,2, ]", |a| a.push(integer("3").unwrap()).unwrap());
+        assert_eq!(out, "a = [ 1,2,3, ]");
+        let out = edit("a = []", |a| a.push(integer("1").unwrap()).unwrap());
+        assert_eq!(out, "a = [1]");
+        let out = edit("a = [1]", |a| a.push(integer("2").unwrap()).unwrap());
+        assert_eq!(out, "a = [1, 2]");
+    }
+
+    #[test]
+    fn push_multi_line() {
+        let out = edit(
+            "a = [\n    \"x\",\n    \"y\",\n]\n",
+            |a| a.push(string("\"z\"").unwrap()).unwrap(),
+        );
+        assert_eq!(out, "a = [\n    \"x\",\n    \"y\",\n    \"z\",\n]\n");
+
+        let out = edit("a = [\n  1\n]\n", |a| a.push(integer("2").unwrap()).unwrap());
+        assert_eq!(out, format!("a = [\n  1,{}  2\n]\n", ::NL));
+
+        let out = edit(
+            "a = [\n  1,\n  2 # two\n]\n",
+            |a| a.push(integer("3").unwrap()).unwrap(),
+        );
+        assert_eq!(out, format!("a = [\n  1,\n  2, # two\n  3{}]\n", ::NL));
+    }
+
+    #[test]
+    fn insert() {
+        let out = edit("a = [1, 2]", |a| a.insert(0, integer("0").unwrap()).unwrap());
+        assert_eq!(out, "a = [0, 1, 2]");
+        let out = edit("a = [\n  1,\n  2,\n]", |a| {
+            a.insert(1, integer("5").unwrap()).unwrap()
+        });
+        assert_eq!(out, "a = [\n  1,\n  5,\n  2,\n]");
+        let out = edit("a = [1, 2]", |a| a.insert(2, integer("3").unwrap()).unwrap());
+        assert_eq!(out, "a = [1, 2, 3]");
+    }
+
+    #[test]
+    fn insert_invalid() {
+        let mut doc = Parser::new("a = [1, 2]").parse().unwrap();
+        assert!(doc["a"].insert(3, integer("3").unwrap()).is_err());
+        assert!(doc["a"].push(bool("true").unwrap()).is_err());
+        assert!(doc["a"].push(table().unwrap()).is_err());
+        assert_eq!(doc.as_string(), "a = [1, 2]");
+    }
+
+    #[test]
+    fn remove() {
+        let out = edit("a = [1, 2, 3]", |a| assert!(a.remove_at(0).unwrap().is_integer()));
+        assert_eq!(out, "a = [2, 3]");
+        let out = edit("a = [1, 2, 3]", |a| { a.remove_at(2).unwrap(); });
+        assert_eq!(out, "a = [1, 2]");
+        let out = edit("a = [1, 2, 3, ]", |a| { a.remove_at(2).unwrap(); });
+        assert_eq!(out, "a = [1, 2, ]");
+        let out = edit("a = [1]", |a| { a.remove_at(0).unwrap(); });
+        assert_eq!(out, "a = []");
+    }
+
+    #[test]
+    fn remove_with_comments() {
+        let src = "a = [\n    true,\n    false,     #pretty comment\n    true,\n    false\n]";
+        let out = edit(src, |a| { a.remove_at(1).unwrap(); });
+        assert_eq!(out, "a = [\n    true,\n    true,\n    false\n]");
+
+        let src = "a = [\n  1,\n  # about two\n  2,\n  3, # three\n]";
+        let out = edit(src, |a| { a.remove_at(0).unwrap(); });
+        assert_eq!(out, "a = [\n  # about two\n  2,\n  3, # three\n]");
+        let out = edit(src, |a| { a.remove_at(2).unwrap(); });
+        assert_eq!(out, "a = [\n  1,\n  # about two\n  2,\n]");
+        let src = "a = [\n  1,\n  2 # two\n]";
+        let out = edit(src, |a| { a.remove_at(1).unwrap(); });
+        assert_eq!(out, "a = [\n  1\n]");
+
+        let src = "a = [\n  1, # one\n  2,\n]";
+        let out = edit(src, |a| { a.remove_at(1).unwrap(); });
+        assert_eq!(out, "a = [\n  1, # one\n]");
+    }
+
+    #[test]
+    fn retain_and_clear() {
+        let out = edit("a = [1, 2, 3, 4]", |a| {
+            a.retain(|it| match *it {
+                Item::Integer { val, .. } => val % 2 == 0,
+                _ => true,
+            }).unwrap()
+        });
+        assert_eq!(out, "a = [2, 4]");
+        let out = edit("a = [\n  1,\n  2,\n]", |a| a.clear().unwrap());
+        assert_eq!(out, "a = []");
+    }
+}
@@ -0,0 +1,309 @@
+//! Reading and writing the comments of a `Container` by what they are
+//! attached to.
+//!
+//! The leading comments of an entry are the comment lines right above it,
+//! with no blank line in between. For a table, those lines are stored at the
+//! end of the body of the table before it. The trailing comment of an entry
+//! is the one on the same line, after the value or the header.
+//!
+//! Comments are given and returned as written, starting with `#`.
+
+use items::*;
+use errors::*;
+use container::{is_table_like, Container};
+
+impl<'a> Container<'a> {
+    /// Returns the comment lines right above the entry `key`.
+    pub fn leading_comments(&self, key: &Key<'a>) -> Result<Vec<&'a str>> {
+        let pos = self.position(key)?;
+        let start = self.leading_start(pos);
+        let mut comments = comment_texts(&self.body[start..pos]);
+        if start == 0 || !is_table_like(&self.body[pos].1) {
+            return Ok(comments);
+        }
+        if let Some(tail) = item_tail(&self.body[start - 1].1) {
+            let end = tail.body.len();
+            let mut first = end;
+            while first > 0 && tail.body[first - 1].1.is_comment() {
+                first -= 1;
+            }
+            let mut lines = comment_texts(&tail.body[first..end]);
+            lines.append(&mut comments);
+            comments = lines;
+        }
+        Ok(comments)
+    }
+
+    /// Replaces the comment lines right above the entry `key` with
+    /// `comments`, one per line.
+    pub fn set_leading_comments(&mut self, key: &Key<'a>, comments: &[&'a str]) -> Result<()> {
+        check_comments(comments)?;
+        let pos = self.position(key)?;
+        let indent = self.body[pos].1.trivia().indent;
+        let lines: Vec<_> = comments.iter().map(|c| comment(indent, c)).collect();
+
+        if is_table_like(&self.body[pos].1) {
+            let idx = self.section_index(key)?;
+            let (mut sections, gaps) = self.detach();
+            sections[idx].comments = lines;
+            self.attach(sections, gaps);
+        } else {
+            let start = self.leading_start(pos);
+            self.body.splice(start..pos, lines.into_iter().map(|c| (None, c)));
+            self.rebuild_map();
+        }
+        Ok(())
+    }
+
+    /// Returns the comment on the line of the entry `key`, if any.
+    pub fn trailing_comment(&self, key: &Key<'a>) -> Result<Option<&'a str>> {
+        let pos = self.position(key)?;
+        let comment = match self.body[pos].1 {
+            Item::AoT(ref tables) => tables.first().map_or("", |t| t.trivia().comment),
+            ref item => item.trivia().comment,
+        };
+        Ok(if comment.is_empty() {
+            None
+        } else {
+            Some(comment)
+        })
+    }
+
+    /// Sets or, with `None`, removes the comment on the line of the entry
+    /// `key`.
+    pub fn set_trailing_comment(&mut self, key: &Key<'a>, comment: Option<&'a str>) -> Result<()> {
+        if let Some(comment) = comment {
+            check_comments(&[comment])?;
+        }
+        let pos = self.position(key)?;
+        let trivia = match self.body[pos].1 {
+            Item::AoT(ref mut tables) => match tables.first_mut() {
+                Some(table) => table.trivia_mut(),
+                None => bail!(ErrorKind::APIWrongItem),
+            },
+            ref mut item => item.trivia_mut(),
+        };
+        match comment {
+            Some(comment) => {
+                if trivia.comment_ws.is_empty() {
+                    trivia.comment_ws = " ";
+                }
+                trivia.comment = comment;
+            }
+            None => {
+                trivia.comment_ws = "";
+                trivia.comment = "";
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the comment lines at the top of the document, set apart from
+    /// the rest by a blank line.
+    pub fn header_comments(&self) -> Vec<&'a str> {
+        let end = self.header_end();
+        comment_texts(&self.body[..end])
+    }
+
+    /// Replaces the comment lines at the top of the document with
+    /// `comments`, followed by a blank line.
+    pub fn set_header_comments(&mut self, comments: &[&'a str]) -> Result<()> {
+        check_comments(comments)?;
+        let mut end = self.header_end();
+        if end > 0 && end < self.body.len() && self.body[end].1.is_ws() {
+            end += 1;
+        }
+        let mut lines: Vec<_> = comments.iter().map(|c| (None, comment("", c))).collect();
+        if !lines.is_empty() && end < self.body.len() {
+            lines.push((None, Item::WS(::NL)));
+        }
+        self.body.splice(..end, lines);
+        self.rebuild_map();
+        Ok(())
+    }
+
+    /// Returns the comment lines at the end of the document.
+    pub fn footer_comments(&self) -> Vec<&'a str> {
+        let tail = ::aot::tail(self);
+        let (start, end) = footer(tail);
+        comment_texts(&tail.body[start..end])
+    }
+
+    /// Replaces the comment lines at the end of the document with
+    /// `comments`, set apart from the rest by a blank line.
+    pub fn set_footer_comments(&mut self, comments: &[&'a str]) -> Result<()> {
+        check_comments(comments)?;
+        let tail = ::aot::tail_mut(self);
+        let (mut start, end) = footer(tail);
+        if start < end && start > 0 && tail.body[start - 1].1.is_ws() {
+            start -= 1;
+        }
+        let mut lines = Vec::new();
+        if !comments.is_empty() {
+            if start > 0 {
+                end_line(&mut tail.body[start - 1].1);
+                lines.push((None, Item::WS(::NL)));
+            }
+            lines.extend(comments.iter().map(|c| (None, comment("", c))));
+        }
+        tail.body.splice(start..end, lines);
+        tail.rebuild_map();
+        Ok(())
+    }
+
+    /// Returns the position right after the comment lines at the top of the
+    /// document, or 0 if there are none.
+    pub(crate) fn header_end(&self) -> usize {
+        let end = self.body
+            .iter()
+            .position(|entry| !entry.1.is_comment())
+            .unwrap_or(self.body.len());
+        match self.body.get(end) {
+            Some(&(None, Item::WS(_))) | None => end,
+            Some(_) => 0,
+        }
+    }
+}
+
+/// Returns the range of the comment lines at the end of `tail`, before the
+/// whitespace that ends it.
+fn footer(tail: &Container) -> (usize, usize) {
+    let mut end = tail.body.len();
+    while end > 0 && tail.body[end - 1].1.is_ws() {
+        end -= 1;
+    }
+    let mut start = end;
+    while start > 0 && tail.body[start - 1].1.is_comment() {
+        start -= 1;
+    }
+    (start, end)
+}
+
+/// Returns the container at the very end of the text of a table or AoT.
+fn item_tail<'b, 'a>(item: &'b Item<'a>) -> Option<&'b Container<'a>> {
+    match *item {
+        Item::Table { ref val, .. } => Some(::aot::tail(val)),
+        Item::AoT(ref tables) => tables.last().and_then(item_tail),
+        _ => None,
+    }
+}
+
+/// Returns the text of the comments among `entries`.
+fn comment_texts<'a>(entries: &[(Option<Key<'a>>, Item<'a>)]) -> Vec<&'a str> {
+    entries
+        .iter()
+        .filter_map(|entry| match entry.1 {
+            Item::Comment(ref trivia) => Some(trivia.comment),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns a comment line.
+fn comment<'a>(indent: &'a str, text: &'a str) -> Item<'a> {
+    Item::Comment(Trivia {
+        indent,
+        comment_ws: "",
+        comment: text,
+        trail: ::NL,
+    })
+}
+
+/// Ends the line of `item` if it doesn't end with a newline.
+fn end_line(item: &mut Item) {
+    let trivia = match *item {
+        Item::Comment(ref mut trivia) => trivia,
+        Item::WS(_) | Item::AoT(_) | Item::None => return,
+        ref mut item => item.trivia_mut(),
+    };
+    if !trivia.trail.contains('\n') {
+        trivia.trail = ::NL;
+    }
+}
+
+/// Checks that each of `comments` is a single comment line.
+fn check_comments(comments: &[&str]) -> Result<()> {
+    for comment in comments {
+        if !comment.starts_with('#') || comment.contains('\n') || comment.contains('\r') {
+            bail!(ErrorKind::InvalidComment(comment.to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+    use items::*;
+
+    #[test]
+    fn leading_comments() {
+        let src = "# Header\n\n# About a\n# more\na = 1\n\nb = 2\n# About t\n[t]\nc = 3\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let a = doc.leading_comments(&Key::new("a")).unwrap();
+        assert_eq!(a, vec!["# About a", "# more"]);
+        assert!(doc.leading_comments(&Key::new("b")).unwrap().is_empty());
+        assert_eq!(doc.leading_comments(&Key::new("t")).unwrap(), vec!["# About t"]);
+        assert!(doc.leading_comments(&Key::new("x")).is_err());
+
+        doc.set_leading_comments(&Key::new("a"), &["# A"]).unwrap();
+        doc.set_leading_comments(&Key::new("b"), &["# B"]).unwrap();
+        doc.set_leading_comments(&Key::new("t"), &[]).unwrap();
+        let expected = format!("# Header\n\n# A{nl}a = 1\n\n# B{nl}b = 2\n[t]\nc = 3\n", nl = ::NL);
+        assert_eq!(doc.as_string(), expected);
+        assert_eq!(doc["b"].as_string(), "2");
+        assert!(doc.set_leading_comments(&Key::new("a"), &["A"]).is_err());
+    }
+
+    #[test]
+    fn table_comments() {
+        let src = "[a]\nx = 1\n\n[b]\ny = 2\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.set_leading_comments(&Key::new("b"), &["# managed"]).unwrap();
+        doc.set_leading_comments(&Key::new("a"), &["# first"]).unwrap();
+        let expected = format!("# first{nl}[a]\nx = 1\n\n# managed{nl}[b]\ny = 2\n", nl = ::NL);
+        assert_eq!(doc.as_string(), expected);
+        assert_eq!(doc.leading_comments(&Key::new("b")).unwrap(), vec!["# managed"]);
+        Parser::new(&expected).parse().unwrap();
+    }
+
+    #[test]
+    fn trailing_comment() {
+        let src = "a = 1 # one\nb = 2\n[t] # table\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        assert_eq!(doc.trailing_comment(&Key::new("a")).unwrap(), Some("# one"));
+        assert_eq!(doc.trailing_comment(&Key::new("b")).unwrap(), None);
+        assert_eq!(doc.trailing_comment(&Key::new("t")).unwrap(), Some("# table"));
+        doc.set_trailing_comment(&Key::new("a"), None).unwrap();
+        doc.set_trailing_comment(&Key::new("b"), Some("# two")).unwrap();
+        doc.set_trailing_comment(&Key::new("t"), Some("# managed")).unwrap();
+        assert_eq!(doc.as_string(), "a = 1\nb = 2 # two\n[t] # managed\n");
+        assert!(doc.set_trailing_comment(&Key::new("a"), Some("# a\nb = 1")).is_err());
+    }
+
+    #[test]
+    fn header_comments() {
+        let mut doc = Parser::new("# About a\na = 1\n").parse().unwrap();
+        assert!(doc.header_comments().is_empty());
+        doc.set_header_comments(&["# managed by tool X"]).unwrap();
+        let expected = format!("# managed by tool X{nl}{nl}# About a\na = 1\n", nl = ::NL);
+        assert_eq!(doc.as_string(), expected);
+        assert_eq!(doc.header_comments(), vec!["# managed by tool X"]);
+        assert_eq!(doc["a"].as_string(), "1");
+        doc.set_header_comments(&[]).unwrap();
+        assert_eq!(doc.as_string(), "# About a\na = 1\n");
+    }
+
+    #[test]
+    fn footer_comments() {
+        let src = "a = 1\n[t]\nb = 2\n\n# end\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        assert_eq!(doc.footer_comments(), vec!["# end"]);
+        doc.set_footer_comments(&[]).unwrap();
+        assert_eq!(doc.as_string(), "a = 1\n[t]\nb = 2\n");
+        assert!(doc.footer_comments().is_empty());
+        doc.set_footer_comments(&["# managed by tool X"]).unwrap();
+        let expected = format!("a = 1\n[t]\nb = 2\n{nl}# managed by tool X{nl}", nl = ::NL);
+        assert_eq!(doc.as_string(), expected);
+    }
+}
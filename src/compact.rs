@@ -0,0 +1,236 @@
+//! Compact output of a `Container`.
+//!
+//! The compact text holds the values of the container and nothing else: no
+//! comments, no whitespace but the newlines that end the key/values, and
+//! each value in its shortest form. The tables are regrouped by segment, and
+//! each one is written either inline or under headers of its own, after the
+//! values of the document, whichever is shorter: `a={b={c={x=1}}}` becomes
+//! `[a.b.c]` and `x=1`, leaving the tables that only hold tables for their
+//! headers to define.
+
+use items::*;
+use container::Container;
+use tree::{entries, Node};
+
+impl<'a> Container<'a> {
+    /// Returns the shortest text that parses to the values of the
+    /// container, leaving out its comments and formatting.
+    ///
+    /// ```
+    /// use Molten::parser::Parser;
+    ///
+    /// let doc = Parser::new("# Server\n[server]\nhost = \"x\"  # main\nports = [ 80, 443 ]\n")
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(doc.to_compact_string(), "server={host=\"x\",ports=[80,443]}\n");
+    /// ```
+    pub fn to_compact_string(&self) -> String {
+        let (body, sections) = split(&forms("", &entries(self, 0, None)));
+        body + &sections
+    }
+}
+
+/// Returns the key/value line of each of the `entries` of the table found at
+/// `path`, along with its text under headers of its own if it has one.
+fn forms(path: &str, entries: &[(String, Node)]) -> Vec<(String, Option<String>)> {
+    entries
+        .iter()
+        .map(|(name, node)| {
+            let path = if path.is_empty() {
+                key(name)
+            } else {
+                format!("{}.{}", path, key(name))
+            };
+            (format!("{}={}\n", key(name), text(node)), section(&path, node))
+        })
+        .collect()
+}
+
+/// Returns the key/values of a table and the sections that follow them,
+/// given the `forms` of its entries, each written in its shortest form.
+fn split(forms: &[(String, Option<String>)]) -> (String, String) {
+    let mut body = String::new();
+    let mut sections = String::new();
+    for (line, section) in forms {
+        match *section {
+            Some(ref section) if section.len() < line.len() => sections.push_str(section),
+            _ => body.push_str(line),
+        }
+    }
+    (body, sections)
+}
+
+/// Returns the shortest text of the table or AoT `node` under headers of
+/// its own, or `None` if it is neither.
+fn section(path: &str, node: &Node) -> Option<String> {
+    match *node {
+        Node::Table(ref entries, _) => {
+            let forms = forms(path, entries);
+            let (body, sections) = split(&forms);
+            let header = format!("[{}]\n{}{}", path, body, sections);
+            // A table that only holds tables is defined by their headers.
+            let implicit: Option<String> = if forms.is_empty() {
+                None
+            } else {
+                forms.into_iter().map(|form| form.1).collect()
+            };
+            match implicit {
+                Some(implicit) if implicit.len() < header.len() => Some(implicit),
+                _ => Some(header),
+            }
+        }
+        Node::Array(ref items, _) if !items.is_empty() => {
+            let mut text = String::new();
+            for item in items {
+                let entries = match *item {
+                    Node::Table(ref entries, _) => entries,
+                    _ => return None,
+                };
+                let (body, sections) = split(&forms(path, entries));
+                text.push_str(&format!("[[{}]]\n{}{}", path, body, sections));
+            }
+            Some(text)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the compact text of `node`.
+fn text(node: &Node) -> String {
+    match *node {
+        Node::Value(item, _) => value(item),
+        Node::Table(ref entries, _) => {
+            let entries: Vec<_> = entries
+                .iter()
+                .map(|(name, node)| format!("{}={}", key(name), text(node)))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        }
+        Node::Array(ref items, _) => {
+            let items: Vec<_> = items.iter().map(text).collect();
+            format!("[{}]", items.join(","))
+        }
+    }
+}
+
+/// Returns the shortest form of a key.
+fn key(name: &str) -> String {
+    Key::for_name(name).as_string()
+}
+
+/// Returns the shortest form of a scalar value.
+fn value(item: &Item) -> String {
+    match *item {
+        Item::Integer { val, raw, .. } => shortest(digits(raw), val.to_string()),
+        Item::Float { val, raw, .. } if val.is_finite() => {
+            shortest(digits(raw), format!("{:?}", val))
+        }
+        Item::Float { raw, .. } => raw.trim_start_matches('+').to_string(),
+        Item::DateTime { raw, .. } => match raw.strip_suffix("+00:00") {
+            Some(utc) => format!("{}Z", utc),
+            None => raw.to_string(),
+        },
+        Item::Str { .. } => quote(&item.as_str().unwrap_or_default()),
+        _ => item.as_string(),
+    }
+}
+
+/// Returns the digits of a number without underscores, nor a plus sign.
+fn digits(raw: &str) -> String {
+    raw.trim_start_matches('+').replace('_', "")
+}
+
+fn shortest(a: String, b: String) -> String {
+    if b.chars().count() < a.chars().count() {
+        b
+    } else {
+        a
+    }
+}
+
+/// Returns the shortest string that holds `s`.
+fn quote(s: &str) -> String {
+    let control = |c: char| c.is_control() && c != '\t';
+    let mut best = basic(s);
+    if !s.contains('\'') && !s.chars().any(control) {
+        best = shortest(best, format!("'{}'", s));
+    }
+    // A multi-line literal string drops the newline right after its
+    // opening quotes, and cannot end with a quote.
+    let multiline = s.contains('\n') && !s.contains("'''") && !s.ends_with('\'') &&
+        !s.chars().any(|c| control(c) && c != '\n');
+    if multiline {
+        let lead = if s.starts_with('\n') { "\n" } else { "" };
+        best = shortest(best, format!("'''{}{}'''", lead, s));
+    }
+    best
+}
+
+/// Returns `s` as a basic string.
+fn basic(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\u{8}' => quoted.push_str("\\b"),
+            '\u{c}' => quoted.push_str("\\f"),
+            c if c.is_control() && c != '\t' => {
+                quoted.push_str(&format!("\\u{:04X}", c as u32))
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+
+    #[test]
+    fn compact() {
+        let src = "# Top\ntitle = \"Tom's \\\"x\\\"\"   # c\nbig = 1_000_000\nn = +1_7\n\
+                   f = 3.140_000\nlines = \"\"\"\na\nb\"\"\"\nwhen = 1979-05-27T07:32:00+00:00\n\
+                   arr = [ 1,  # one\n  2 ]\n\n[a.b]\nc = { d = 'e' }\n\n[a]\nx = 1\n\n\
+                   [[bin]]\nname = 'p'\n[bin.extra]\ny = true\n\n[[bin]]\nname = 'q'\n\n[empty]\n";
+        let doc = Parser::new(src).parse().unwrap();
+        let compact = doc.to_compact_string();
+        assert_eq!(
+            compact,
+            "title=\"Tom's \\\"x\\\"\"\nbig=1000000\nn=17\nf=3.14\nlines=\"a\\nb\"\n\
+             when=1979-05-27T07:32:00Z\narr=[1,2]\na={b={c={d=\"e\"}},x=1}\n\
+             bin=[{name=\"p\",extra={y=true}},{name=\"q\"}]\n[empty]\n"
+        );
+
+        let again = Parser::new(&compact).parse().unwrap();
+        assert_eq!(again.to_compact_string(), compact);
+        assert_eq!(again["title"].as_str().unwrap(), "Tom's \"x\"");
+        assert_eq!(again["lines"].as_str().unwrap(), "a\nb");
+        assert_eq!(again["a"]["b"]["c"]["d"].as_str().unwrap(), "e");
+
+        // Headers are shorter for deeply nested tables, and follow the values.
+        let doc = Parser::new("z = 3\n[a.b.c]\nx = 1\n\n[t]\ny = 2\n").parse().unwrap();
+        assert_eq!(doc.to_compact_string(), "z=3\nt={y=2}\n[a.b.c]\nx=1\n");
+
+        let doc = Parser::new("a = [+inf, -inf]\nb = +nan\n").parse().unwrap();
+        assert_eq!(doc.to_compact_string(), "a=[inf,-inf]\nb=nan\n");
+        let compact = doc.to_compact_string();
+        let again = Parser::new(&compact).parse().unwrap();
+        assert_eq!(again.to_compact_string(), compact);
+    }
+
+    #[test]
+    fn quoting() {
+        let src = "s = \"C:\\\\dir\"\nt = \"\"\"\n\\\"a\\\"\n\n\nb\\\\\"\"\"\n";
+        let doc = Parser::new(src).parse().unwrap();
+        assert_eq!(doc.to_compact_string(), "s='C:\\dir'\nt='''\"a\"\n\n\nb\\'''\n");
+        let compact = doc.to_compact_string();
+        let again = Parser::new(&compact).parse().unwrap();
+        assert_eq!(again["t"].as_str().unwrap(), doc["t"].as_str().unwrap());
+    }
+}
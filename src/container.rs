@@ -1,6 +1,8 @@
 //! Container to hold items within a `TOMLDocument`.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::{slice, vec};
 
 use items::*;
 use errors::*;
@@ -25,25 +27,310 @@ impl<'a> Container<'a> {
     pub fn append<K: Into<Option<Key<'a>>>>(&mut self, _key: K, item: Item<'a>) -> Result<()> {
         let key = _key.into();
         if let Some(k) = key.clone() {
-            // TODO: Fix AoT
-            // if self.map.contains_key(&k) {
-            //     bail!(ErrorKind::DuplicateKey(k.key.into()));
-            // }
+            if let Some(&idx) = self.map.get(&k) {
+                match (&self.body[idx].1, &item) {
+                    // A new entry of an existing AoT joins its siblings.
+                    (&Item::AoT(_), &Item::Table { is_aot_elem: true, .. }) => {
+                        return self.body[idx].1.push(item);
+                    }
+                    // The entries of an AoT can be split by other tables.
+                    (&Item::AoT(_), &Item::AoT(_)) => {}
+                    _ => bail!(ErrorKind::DuplicateKey(k.key.to_string())),
+                }
+            }
             self.map.insert(k, self.body.len());
         }
         self.body.push((key, item));
         Ok(())
     }
 
-    pub(crate) fn remove(&mut self, key: &Key<'a>) -> Result<()> {
-        let idx = self.map.get(key).ok_or(
-            ErrorKind::NonExistentKey(key.as_string()),
-        )?;
-        self.body[*idx] = (None, Item::None);
+    /// Inserts a (key, item) pair where it belongs in the container.
+    ///
+    /// A value is placed right after the last key/value of the container,
+    /// so that it comes before any trailing whitespace, comments and child
+    /// tables. Tables go at the end of the container.
+    pub fn insert<K: Into<Option<Key<'a>>>>(&mut self, key: K, item: Item<'a>) -> Result<()> {
+        let pos = self.insertion_point(&item);
+        self.insert_raw(pos, key.into(), item)
+    }
+
+    /// Inserts a (key, item) pair before the entry `before`, and before the
+    /// comments directly above it.
+    pub fn insert_before<K: Into<Option<Key<'a>>>>(
+        &mut self,
+        before: &Key<'a>,
+        key: K,
+        item: Item<'a>,
+    ) -> Result<()> {
+        let idx = self.position(before)?;
+        let pos = self.leading_start(idx);
+        self.insert_raw(pos, key.into(), item)
+    }
+
+    /// Inserts a (key, item) pair right after the entry `after`.
+    pub fn insert_after<K: Into<Option<Key<'a>>>>(
+        &mut self,
+        after: &Key<'a>,
+        key: K,
+        item: Item<'a>,
+    ) -> Result<()> {
+        let pos = self.position(after)? + 1;
+        self.insert_raw(pos, key.into(), item)
+    }
+
+    /// Inserts a (key, item) pair so that it becomes the entry at index
+    /// `idx`, as counted by `Index<usize>`.
+    pub fn insert_at<K: Into<Option<Key<'a>>>>(
+        &mut self,
+        idx: usize,
+        key: K,
+        item: Item<'a>,
+    ) -> Result<()> {
+        let pos = match self.body.iter().enumerate().filter(|e| (e.1).0.is_some()).nth(idx) {
+            Some((pos, _)) => self.leading_start(pos),
+            None if idx == self.map.len() => self.body.len(),
+            None => bail!(ErrorKind::IndexOutOfBounds(idx)),
+        };
+        self.insert_raw(pos, key.into(), item)
+    }
 
+    /// Returns the position of `key` in the body.
+    pub(crate) fn position(&self, key: &Key<'a>) -> Result<usize> {
+        match self.map.get(key) {
+            Some(&idx) => Ok(idx),
+            None => bail!(ErrorKind::NonExistentKey(key.as_string())),
+        }
+    }
+
+    /// Returns the position of the first of the comments directly above the
+    /// item at `pos`, or `pos` if there are none.
+    pub(crate) fn leading_start(&self, mut pos: usize) -> usize {
+        while pos > 0 && self.body[pos - 1].1.is_comment() {
+            pos -= 1;
+        }
+        pos
+    }
+
+    /// Returns the position at which `insert` places `item`.
+    fn insertion_point(&self, item: &Item<'a>) -> usize {
+        if is_table_like(item) {
+            return self.body.len();
+        }
+        let last_value = self.body
+            .iter()
+            .rposition(|entry| entry.0.is_some() && !is_table_like(&entry.1));
+        if let Some(pos) = last_value {
+            return pos + 1;
+        }
+        // No values yet: go before the first table, and before the comments
+        // and whitespace that lead up to it.
+        let first_table = self.body
+            .iter()
+            .position(|entry| is_table_like(&entry.1))
+            .unwrap_or(self.body.len());
+        let mut pos = self.leading_start(first_table);
+        while pos > 0 && self.body[pos - 1].1.is_ws() {
+            pos -= 1;
+        }
+        // The header comments of the document stay on top.
+        pos.max(self.header_end())
+    }
+
+    /// Returns `true` if the items of the container are laid out on
+    /// separate lines, as opposed to those of an inline table.
+    fn is_multiline(&self) -> bool {
+        self.body.iter().any(|entry| match entry.1 {
+            Item::WS(_) | Item::Comment(_) | Item::AoT(_) | Item::Table { .. } => true,
+            Item::None => false,
+            ref v => v.trivia().trail.contains('\n'),
+        })
+    }
+
+    /// Inserts a (key, item) pair at position `pos` of the body, shifting
+    /// the items that follow.
+    ///
+    /// Values can't follow tables within a container, and the line breaks
+    /// around the new item are fixed up so that it gets a line of its own.
+    fn insert_raw(&mut self, pos: usize, key: Option<Key<'a>>, mut item: Item<'a>) -> Result<()> {
+        if let Some(ref k) = key {
+            if self.map.contains_key(k) {
+                bail!(ErrorKind::DuplicateKey(k.key.to_string()));
+            }
+        }
+        let valid = if is_table_like(&item) {
+            !self.body[pos..].iter().any(|entry| entry.0.is_some() && !is_table_like(&entry.1))
+        } else if key.is_some() {
+            !self.body[..pos].iter().any(|entry| is_table_like(&entry.1))
+        } else {
+            true
+        };
+        if !valid {
+            bail!(ErrorKind::InvalidPosition);
+        }
+
+        if key.is_some() && !item.is_aot() {
+            let multiline = self.is_multiline() || item.trivia().trail.contains('\n');
+            if multiline {
+                if pos < self.body.len() && !item.trivia().trail.contains('\n') {
+                    item.trivia_mut().trail = ::NL;
+                }
+                if pos > 0 {
+                    match self.body[pos - 1] {
+                        (Some(_), ref mut prev)
+                            if !is_table_like(prev) && !prev.trivia().trail.contains('\n') =>
+                        {
+                            prev.trivia_mut().trail = ::NL;
+                        }
+                        (None, Item::Comment(ref mut trivia)) if !trivia.trail.contains('\n') => {
+                            trivia.trail = ::NL;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            // Follow the indentation of the neighbouring values.
+            if item.trivia().indent.is_empty() && !is_table_like(&item) {
+                let sibling = self.body[..pos]
+                    .iter()
+                    .rev()
+                    .chain(self.body[pos..].iter())
+                    .find(|entry| entry.0.is_some() && !is_table_like(&entry.1));
+                if let Some(entry) = sibling {
+                    item.trivia_mut().indent = entry.1.trivia().indent;
+                }
+            }
+        }
+
+        // A value right below the header comments would join them: a blank
+        // line keeps them apart, as in the layout the parser reads.
+        let blank = key.is_some() && !is_table_like(&item) && pos > 0 && pos == self.header_end();
+        let shift = if blank { 2 } else { 1 };
+        for idx in self.map.values_mut() {
+            if *idx >= pos {
+                *idx += shift;
+            }
+        }
+        if blank {
+            self.body.insert(pos, (None, Item::WS(::NL)));
+        }
+        let pos = pos + shift - 1;
+        if let Some(ref k) = key {
+            self.map.insert(k.clone(), pos);
+        }
+        self.body.insert(pos, (key, item));
         Ok(())
     }
 
+    /// Removes the entry `key` from the container and returns it.
+    ///
+    /// The comments and whitespace around the entry are left in place; see
+    /// `remove_with` to take them out too.
+    pub fn remove(&mut self, key: &Key<'a>) -> Result<(Key<'a>, Item<'a>)> {
+        self.remove_with(key, RemoveOptions::default())
+    }
+
+    /// Removes the entry `key` from the container, along with the trivia
+    /// selected by `options`, and returns it.
+    pub fn remove_with(
+        &mut self,
+        key: &Key<'a>,
+        options: RemoveOptions,
+    ) -> Result<(Key<'a>, Item<'a>)> {
+        let pos = self.position(key)?;
+        let start = if options.comments {
+            self.leading_start(pos)
+        } else {
+            pos
+        };
+        let mut end = pos + 1;
+        if options.whitespace && (start == 0 || self.body[start - 1].1.is_ws()) {
+            while end < self.body.len() && self.body[end].1.is_ws() {
+                end += 1;
+            }
+        }
+
+        let mut removed: Vec<_> = self.body.drain(start..end).collect();
+        self.rebuild_map();
+        let (k, v) = removed.swap_remove(pos - start);
+        Ok((k.unwrap(), v))
+    }
+
+    /// Replaces the value of the entry `key` and returns the old one.
+    ///
+    /// The key, its separator and the decoration of the old value are kept;
+    /// see `Item::set_value`.
+    pub fn replace(&mut self, key: &Key<'a>, value: Item<'a>) -> Result<Item<'a>> {
+        let pos = self.position(key)?;
+        self.body[pos].1.set_value(value)
+    }
+
+    /// Renames the entry `old` to `new`.
+    ///
+    /// The new name is quoted if it cannot be written as a bare key. A
+    /// key/value keeps its separator and the decoration of its value. For a
+    /// table or an AoT, only the last segment of the dotted header name
+    /// changes, and the headers of its child tables follow.
+    pub fn rename_key<K: Into<Cow<'a, str>>>(&mut self, old: &Key<'a>, new: K) -> Result<()> {
+        let pos = self.position(old)?;
+        let mut key = Key::for_name(new);
+        let current = self.body[pos].0.clone().unwrap();
+        key.sep = current.sep;
+        if is_table_like(&self.body[pos].1) {
+            let (start, end) = last_segment(&current.key);
+            let name = format!(
+                "{}{}{}",
+                &current.key[..start],
+                key.as_string(),
+                &current.key[end..]
+            );
+            key.t = current.t.clone();
+            key.key = name.into();
+        }
+
+        if key == current {
+            return Ok(());
+        }
+        if self.map.contains_key(&key) {
+            bail!(ErrorKind::DuplicateKey(key.key.to_string()));
+        }
+        // The tables nested in a table may also come after other tables, as
+        // siblings of their parent.
+        let mut children = Vec::new();
+        for (pos, entry) in self.body.iter().enumerate() {
+            if let (Some(ref k), true) = (&entry.0, is_table_like(&entry.1)) {
+                if let Some(name) = renamed_child(&k.key, &current.key, &key.key) {
+                    let child = Key { key: name.into(), ..k.clone() };
+                    if self.map.contains_key(&child) {
+                        bail!(ErrorKind::DuplicateKey(child.key.to_string()));
+                    }
+                    children.push((pos, child));
+                }
+            }
+        }
+        for (pos, child) in children {
+            rename_children(&mut self.body[pos].1, &current.key, &key.key);
+            self.body[pos].0 = Some(child);
+        }
+        for entry in &mut self.body {
+            if entry.0.as_ref() == Some(&current) {
+                rename_children(&mut entry.1, &current.key, &key.key);
+                entry.0 = Some(key.clone());
+            }
+        }
+        self.rebuild_map();
+        Ok(())
+    }
+
+    /// Rebuilds the index map from the body.
+    pub(crate) fn rebuild_map(&mut self) {
+        self.map.clear();
+        for (idx, entry) in self.body.iter().enumerate() {
+            if let Some(ref k) = entry.0 {
+                self.map.insert(k.clone(), idx);
+            }
+        }
+    }
+
     // Returns a mutable reference to the item that was most recently been added to the container.
     pub(crate) fn last_item_mut(&mut self) -> Option<&mut Item<'a>> {
         self.body.last_mut().map(|&mut (_, ref mut v)| v)
@@ -109,12 +396,59 @@ impl<'a> Container<'a> {
         s
     }
 
-    /// Returns a container iterator.
-    pub fn iter(&'a self) -> ContainerIterator<'a> {
-        ContainerIterator {
-            container: self,
-            current: 0,
-        }
+    /// Returns an iterator over the (key, item) pairs of the container,
+    /// skipping whitespace and comments.
+    pub fn iter(&self) -> ContainerIterator<'_, 'a> {
+        ContainerIterator { entries: self.body.iter() }
+    }
+
+    /// Returns an iterator over the (key, mutable item) pairs of the
+    /// container, skipping whitespace and comments.
+    pub fn iter_mut(&mut self) -> ContainerIteratorMut<'_, 'a> {
+        ContainerIteratorMut { entries: self.body.iter_mut() }
+    }
+
+    /// Returns an iterator over the keys of the container.
+    pub fn keys(&self) -> ContainerKeys<'_, 'a> {
+        ContainerKeys { inner: self.iter() }
+    }
+
+    /// Returns an iterator over the items of the container, skipping
+    /// whitespace and comments.
+    pub fn values(&self) -> ContainerValues<'_, 'a> {
+        ContainerValues { inner: self.iter() }
+    }
+
+    /// Returns the number of (key, item) pairs in the container.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if the container holds no (key, item) pairs.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Keeps only the (key, item) pairs for which `f` returns `true`.
+    ///
+    /// As with `remove`, the comments and whitespace around the removed
+    /// entries are left in place.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&Key<'a>, &mut Item<'a>) -> bool,
+    {
+        self.body.retain_mut(|entry| match *entry {
+            (Some(ref k), ref mut v) if v.is_value() => f(k, v),
+            _ => true,
+        });
+        self.rebuild_map();
+    }
+
+    /// Empties the container, and returns an iterator over the (key, item)
+    /// pairs it held. Whitespace and comments are dropped.
+    pub fn drain(&mut self) -> ContainerDrain<'_, 'a> {
+        self.map.clear();
+        ContainerDrain { entries: self.body.drain(..) }
     }
 
     /// Returns an exhauseive container iterator.
@@ -126,31 +460,209 @@ impl<'a> Container<'a> {
     }
 }
 
-/// An iterator that returns the items in the container.
-#[derive(Debug)]
-pub struct ContainerIterator<'a> {
-    container: &'a Container<'a>,
-    current: usize,
+/// The trivia to take out of a container along with a removed entry.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RemoveOptions {
+    /// Also remove the comment lines directly above the entry.
+    pub comments: bool,
+    /// Also remove the blank lines that follow the entry when they would
+    /// otherwise be doubled, or left at the start of the container.
+    pub whitespace: bool,
 }
 
-impl<'a> Iterator for ContainerIterator<'a> {
-    type Item = &'a Item<'a>;
+/// Returns `true` if `item` is rendered with a header of its own.
+pub(crate) fn is_table_like(item: &Item) -> bool {
+    matches!(*item, Item::Table { .. } | Item::AoT(_))
+}
 
-    // TODO: There must be a better way
-    fn next(&mut self) -> Option<&'a Item<'a>> {
-        loop {
-            if self.current == self.container.body.len() {
-                return None;
-            }
-            match self.container.body[self.current].1.is_value() {
-                true => {
-                    let r = Some(&self.container.body[self.current].1);
-                    self.current += 1;
-                    return r;
+/// Returns the byte range of the last segment of the dotted name `name`,
+/// without the whitespace around it.
+pub(crate) fn last_segment(name: &str) -> (usize, usize) {
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in name.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '.') => start = i + 1,
+            _ => {}
+        }
+    }
+    let segment = name[start..].trim();
+    let start = start + name[start..].find(segment).unwrap_or(0);
+    (start, start + segment.len())
+}
+
+/// Replaces the prefix `old` by `new` in the names of the tables nested in
+/// `item`.
+fn rename_children<'a>(item: &mut Item<'a>, old: &str, new: &str) {
+    let containers: Vec<&mut Container<'a>> = match *item {
+        Item::Table { ref mut val, .. } => vec![val],
+        Item::AoT(ref mut tables) => tables
+            .iter_mut()
+            .filter_map(|table| table.as_table_mut().ok())
+            .collect(),
+        _ => return,
+    };
+    for container in containers {
+        for entry in &mut container.body {
+            if let Some(ref mut key) = entry.0 {
+                if is_table_like(&entry.1) {
+                    if let Some(name) = renamed_child(&key.key, old, new) {
+                        key.key = name.into();
+                    }
                 }
-                false => self.current += 1,
             }
+            rename_children(&mut entry.1, old, new);
         }
+        container.rebuild_map();
+    }
+}
+
+/// Returns `name` with the prefix `old` replaced by `new` if it is the name
+/// of a table nested in the table `old`.
+fn renamed_child(name: &str, old: &str, new: &str) -> Option<String> {
+    let rest = name.strip_prefix(old)?;
+    if rest.trim_start().starts_with('.') {
+        Some(format!("{}{}", new, rest))
+    } else {
+        None
+    }
+}
+
+/// The type of the entries of a container body.
+type Entry<'a> = (Option<Key<'a>>, Item<'a>);
+
+/// Returns the key and item of `entry` unless it is whitespace or a comment.
+fn pair<'b, 'a>(entry: &'b Entry<'a>) -> Option<(&'b Key<'a>, &'b Item<'a>)> {
+    match *entry {
+        (Some(ref k), ref v) if v.is_value() => Some((k, v)),
+        _ => None,
+    }
+}
+
+/// An iterator over the (key, item) pairs of a container.
+#[derive(Debug)]
+pub struct ContainerIterator<'b, 'a: 'b> {
+    entries: slice::Iter<'b, Entry<'a>>,
+}
+
+impl<'b, 'a: 'b> Iterator for ContainerIterator<'b, 'a> {
+    type Item = (&'b Key<'a>, &'b Item<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.by_ref().filter_map(pair).next()
+    }
+}
+
+/// An iterator over the (key, mutable item) pairs of a container.
+#[derive(Debug)]
+pub struct ContainerIteratorMut<'b, 'a: 'b> {
+    entries: slice::IterMut<'b, Entry<'a>>,
+}
+
+impl<'b, 'a: 'b> Iterator for ContainerIteratorMut<'b, 'a> {
+    type Item = (&'b Key<'a>, &'b mut Item<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries
+            .by_ref()
+            .filter_map(|entry| match *entry {
+                (Some(ref k), ref mut v) if v.is_value() => Some((k, v)),
+                _ => None,
+            })
+            .next()
+    }
+}
+
+/// An iterator over the keys of a container.
+#[derive(Debug)]
+pub struct ContainerKeys<'b, 'a: 'b> {
+    inner: ContainerIterator<'b, 'a>,
+}
+
+impl<'b, 'a: 'b> Iterator for ContainerKeys<'b, 'a> {
+    type Item = &'b Key<'a>;
+
+    fn next(&mut self) -> Option<&'b Key<'a>> {
+        self.inner.next().map(|(k, _)| k)
+    }
+}
+
+/// An iterator over the items of a container.
+#[derive(Debug)]
+pub struct ContainerValues<'b, 'a: 'b> {
+    inner: ContainerIterator<'b, 'a>,
+}
+
+impl<'b, 'a: 'b> Iterator for ContainerValues<'b, 'a> {
+    type Item = &'b Item<'a>;
+
+    fn next(&mut self) -> Option<&'b Item<'a>> {
+        self.inner.next().map(|(_, v)| v)
+    }
+}
+
+/// A draining iterator over the (key, item) pairs of a container.
+#[derive(Debug)]
+pub struct ContainerDrain<'b, 'a: 'b> {
+    entries: vec::Drain<'b, Entry<'a>>,
+}
+
+impl<'b, 'a: 'b> Iterator for ContainerDrain<'b, 'a> {
+    type Item = (Key<'a>, Item<'a>);
+
+    fn next(&mut self) -> Option<(Key<'a>, Item<'a>)> {
+        self.entries.by_ref().filter_map(owned_pair).next()
+    }
+}
+
+/// An owning iterator over the (key, item) pairs of a container.
+#[derive(Debug)]
+pub struct ContainerIntoIterator<'a> {
+    entries: vec::IntoIter<Entry<'a>>,
+}
+
+impl<'a> Iterator for ContainerIntoIterator<'a> {
+    type Item = (Key<'a>, Item<'a>);
+
+    fn next(&mut self) -> Option<(Key<'a>, Item<'a>)> {
+        self.entries.by_ref().filter_map(owned_pair).next()
+    }
+}
+
+/// Returns the key and item of `entry` unless it is whitespace or a comment.
+fn owned_pair(entry: Entry) -> Option<(Key, Item)> {
+    match entry {
+        (Some(k), v) if v.is_value() => Some((k, v)),
+        _ => None,
+    }
+}
+
+impl<'b, 'a: 'b> IntoIterator for &'b Container<'a> {
+    type Item = (&'b Key<'a>, &'b Item<'a>);
+    type IntoIter = ContainerIterator<'b, 'a>;
+
+    fn into_iter(self) -> ContainerIterator<'b, 'a> {
+        self.iter()
+    }
+}
+
+impl<'b, 'a: 'b> IntoIterator for &'b mut Container<'a> {
+    type Item = (&'b Key<'a>, &'b mut Item<'a>);
+    type IntoIter = ContainerIteratorMut<'b, 'a>;
+
+    fn into_iter(self) -> ContainerIteratorMut<'b, 'a> {
+        self.iter_mut()
+    }
+}
+
+impl<'a> IntoIterator for Container<'a> {
+    type Item = (Key<'a>, Item<'a>);
+    type IntoIter = ContainerIntoIterator<'a>;
+
+    fn into_iter(self) -> ContainerIntoIterator<'a> {
+        ContainerIntoIterator { entries: self.body.into_iter() }
     }
 }
 
@@ -174,3 +686,269 @@ impl<'a> Iterator for ContainerIteratorExhaustive<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::Parser;
+    use api::*;
+
+    #[test]
+    fn insert_after_last_value() {
+        let src = "[dependencies]\na = \"1\"\n# b = \"2\"\n\n[dev-dependencies]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let (k, v) = key_value("c = \"3\"\n").unwrap();
+        doc["dependencies"].as_table_mut().unwrap().insert(k, v).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "[dependencies]\na = \"1\"\nc = \"3\"\n# b = \"2\"\n\n[dev-dependencies]\n"
+        );
+    }
+
+    #[test]
+    fn insert_before_tables() {
+        let src = "# Header\n\n[package]\nname = \"x\"\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.insert(Key::new("top"), integer("1").unwrap()).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            format!("# Header\n{nl}top = 1{nl}\n[package]\nname = \"x\"\n", nl = ::NL)
+        );
+        assert_eq!(doc[0], doc["top"]);
+        assert!(doc["package"].is_table());
+        assert_eq!(doc.header_comments(), vec!["# Header"]);
+
+        let mut doc = Parser::new("# Header\n").parse().unwrap();
+        doc.insert_at(0, Key::new("top"), integer("1").unwrap()).unwrap();
+        assert_eq!(doc.as_string(), format!("# Header\n{nl}top = 1{nl}", nl = ::NL));
+        let mut doc = Parser::new("# Header\n").parse().unwrap();
+        doc.insert(Key::new("top"), integer("1").unwrap()).unwrap();
+        assert_eq!(doc.as_string(), format!("# Header\n{nl}top = 1{nl}", nl = ::NL));
+    }
+
+    #[test]
+    fn insert_follows_layout() {
+        let src = "[t]\n    a = 1\n    b = 2";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc["t"]
+            .as_table_mut()
+            .unwrap()
+            .insert(Key::new("c"), value("3").unwrap())
+            .unwrap();
+        assert_eq!(doc.as_string(), format!("[t]\n    a = 1\n    b = 2{}    c = 3", ::NL));
+    }
+
+    #[test]
+    fn insert_before_and_after() {
+        let src = "a = 1\n# about c\nc = 3\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let c = Key::new("c");
+        doc.insert_before(&c, Key::new("b"), integer("2").unwrap()).unwrap();
+        doc.insert_after(&c, Key::new("d"), integer("4").unwrap()).unwrap();
+        let expected = format!("a = 1\nb = 2{nl}# about c\nc = 3\nd = 4{nl}", nl = ::NL);
+        assert_eq!(doc.as_string(), expected);
+        assert_eq!(doc["c"].as_string(), "3");
+        assert_eq!(doc[3], doc["d"]);
+    }
+
+    #[test]
+    fn insert_at() {
+        let src = "a = 1\nc = 3\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.insert_at(1, Key::new("b"), integer("2").unwrap()).unwrap();
+        doc.insert_at(3, Key::new("d"), integer("4").unwrap()).unwrap();
+        let expected = format!("a = 1\nb = 2{nl}c = 3\nd = 4{nl}", nl = ::NL);
+        assert_eq!(doc.as_string(), expected);
+        assert!(doc.insert_at(9, Key::new("e"), integer("5").unwrap()).is_err());
+    }
+
+    #[test]
+    fn insert_invalid() {
+        let src = "a = 1\n[t]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        assert!(doc.insert(Key::new("a"), integer("2").unwrap()).is_err());
+        let t = Key::new("t");
+        assert!(doc.insert_after(&t, Key::new("b"), integer("2").unwrap()).is_err());
+        assert!(doc.insert_before(&Key::new("a"), Key::new("u"), table().unwrap()).is_err());
+        assert_eq!(doc.as_string(), src);
+    }
+
+    #[test]
+    fn insert_inline() {
+        let src = "t = {a = 1, c = 3}";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc["t"]
+            .as_table_mut()
+            .unwrap()
+            .insert_before(&Key::new("c"), Key::new("b"), value("2").unwrap())
+            .unwrap();
+        assert_eq!(doc.as_string(), "t = {a = 1, b = 2, c = 3}");
+    }
+
+    #[test]
+    fn remove() {
+        let src = "a = 1\nb = 2 # two\nc = 3\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let (k, v) = doc.remove(&Key::new("b")).unwrap();
+        assert_eq!(k.key, "b");
+        assert_eq!(v.trivia().comment, "# two");
+        assert_eq!(doc.as_string(), "a = 1\nc = 3\n");
+        assert_eq!(doc[1], doc["c"]);
+        assert!(doc.remove(&Key::new("b")).is_err());
+
+        let (k, v) = key_value("b = 4\n").unwrap();
+        doc.insert(k, v).unwrap();
+        assert_eq!(doc.as_string(), "a = 1\nc = 3\nb = 4\n");
+    }
+
+    #[test]
+    fn remove_with() {
+        let src = "a = 1\n\n# About b\n# and more\nb = 2\n\nc = 3\n";
+        let options = RemoveOptions {
+            comments: true,
+            whitespace: true,
+        };
+
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.remove_with(&Key::new("b"), options).unwrap();
+        assert_eq!(doc.as_string(), "a = 1\n\nc = 3\n");
+
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.remove_with(&Key::new("a"), options).unwrap();
+        assert_eq!(doc.as_string(), "# About b\n# and more\nb = 2\n\nc = 3\n");
+
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.remove(&Key::new("b")).unwrap();
+        assert_eq!(doc.as_string(), "a = 1\n\n# About b\n# and more\n\nc = 3\n");
+    }
+
+    #[test]
+    fn remove_table() {
+        let src = "[a]\nx = 1\n\n[b]\ny = 2\n\n[c]\nz = 3\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let (_, b) = doc.remove(&Key::new("b")).unwrap();
+        assert_eq!(b["y"].as_string(), "2");
+        assert_eq!(doc.as_string(), "[a]\nx = 1\n\n[c]\nz = 3\n");
+    }
+
+    #[test]
+    fn replace() {
+        let src = "[package]\n  version   =   \"1.0\"  # pinned\nedition = 2015\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let old = doc["package"]
+            .as_table_mut()
+            .unwrap()
+            .replace(&Key::new("version"), value("\"1.1\"").unwrap())
+            .unwrap();
+        assert_eq!(old.as_string(), "\"1.0\"");
+        doc["package"]
+            .as_table_mut()
+            .unwrap()
+            .replace(&Key::new("edition"), string("'2018'").unwrap())
+            .unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "[package]\n  version   =   \"1.1\"  # pinned\nedition = '2018'\n"
+        );
+        assert!(doc.replace(&Key::new("missing"), value("1").unwrap()).is_err());
+        assert!(doc.replace(&Key::new("package"), value("1").unwrap()).is_err());
+    }
+
+    #[test]
+    fn rename_key() {
+        let src = "[dev-dependencies]\nfoo   =   \"1\" # why\nbar = \"2\"\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        {
+            let deps = doc["dev-dependencies"].as_table_mut().unwrap();
+            deps.rename_key(&Key::new("foo"), "baz").unwrap();
+            assert!(deps.rename_key(&Key::new("baz"), "bar").is_err());
+            assert!(deps.rename_key(&Key::new("foo"), "qux").is_err());
+            deps.rename_key(&Key::new("bar"), "my crate").unwrap();
+        }
+        assert_eq!(
+            doc.as_string(),
+            "[dev-dependencies]\nbaz   =   \"1\" # why\n\"my crate\" = \"2\"\n"
+        );
+        assert_eq!(doc["dev-dependencies"]["baz"].as_string(), "\"1\"");
+    }
+
+    #[test]
+    fn rename_table() {
+        let src = "[a]\nx = 1\n\n[a.b] # b\ny = 2\n\n[a.b.c]\nz = 3\n\n[d]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.rename_key(&Key::new("a"), "e").unwrap();
+        doc["e"]
+            .as_table_mut()
+            .unwrap()
+            .rename_key(&Key::new("e.b"), "f g")
+            .unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "[e]\nx = 1\n\n[e.\"f g\"] # b\ny = 2\n\n[e.\"f g\".c]\nz = 3\n\n[d]\n"
+        );
+        assert!(doc.rename_key(&Key::new("e"), "d").is_err());
+        Parser::new(&doc.as_string()).parse().unwrap();
+
+        let src = "[a]\nx = 1\n[b]\ny = 2\n[a.c]\nz = 3\n[a.c.d]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.rename_key(&Key::new("a"), "e").unwrap();
+        assert_eq!(doc.as_string(), "[e]\nx = 1\n[b]\ny = 2\n[e.c]\nz = 3\n[e.c.d]\n");
+        assert_eq!(doc["e.c"]["z"].as_string(), "3");
+        assert!(doc.rename_key(&Key::new("e"), "b").is_err());
+        let src = "[a]\n[b]\n[a.c]\n[e.c]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        assert!(doc.rename_key(&Key::new("a"), "e").is_err());
+        assert_eq!(doc.as_string(), src);
+    }
+
+    #[test]
+    fn rename_aot() {
+        let src = "[[bin]]\nname = \"a\"\n[bin.x]\n[[bin]]\nname = \"b\"\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.rename_key(&Key::new("bin"), "example").unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "[[example]]\nname = \"a\"\n[example.x]\n[[example]]\nname = \"b\"\n"
+        );
+        assert!(doc["example"].is_aot());
+    }
+
+    #[test]
+    fn iterate() {
+        let src = "# c\na = 1\n\nb = 2 # two\n[t]\nc = 3\n[[u]]\n[[u]]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let keys: Vec<_> = doc.keys().map(|k| k.as_string()).collect();
+        assert_eq!(keys, vec!["a", "b", "t", "u"]);
+        assert_eq!(doc.len(), 4);
+        assert!(!doc.is_empty());
+        assert!(doc.values().all(|v| v.is_value()));
+        let pairs: Vec<_> = (&doc)
+            .into_iter()
+            .map(|(k, v)| (k.as_string(), v.is_table()))
+            .collect();
+        assert_eq!(pairs[2], ("t".to_string(), true));
+
+        for (_, v) in &mut doc {
+            if v.is_integer() {
+                v.set_value(integer("0").unwrap()).unwrap();
+            }
+        }
+        assert_eq!(doc.as_string(), "# c\na = 0\n\nb = 0 # two\n[t]\nc = 3\n[[u]]\n[[u]]\n");
+
+        doc.retain(|k, _| k.key != "b");
+        assert_eq!(doc.as_string(), "# c\na = 0\n\n[t]\nc = 3\n[[u]]\n[[u]]\n");
+        assert_eq!(doc["t"]["c"].as_string(), "3");
+
+        let drained: Vec<_> = doc.drain().map(|(k, _)| k.as_string()).collect();
+        assert_eq!(drained, vec!["a", "t", "u"]);
+        assert!(doc.is_empty());
+        assert_eq!(doc.as_string(), "");
+    }
+
+    #[test]
+    fn into_iter() {
+        let doc = Parser::new("a = 1\nb = 2\n").parse().unwrap();
+        let values: Vec<_> = doc.into_iter().map(|(_, v)| v.as_string()).collect();
+        assert_eq!(values, vec!["1", "2"]);
+    }
+}
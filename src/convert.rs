@@ -0,0 +1,345 @@
+//! Conversions between inline and standard tables, and between arrays of
+//! inline tables and AoTs.
+//!
+//! Inline tables are laid out on a single line, so the comments and blank
+//! lines of a table are lost when it is made inline. The other way round,
+//! each key/value gets a line of its own.
+
+use items::*;
+use errors::*;
+use container::{last_segment, Container};
+use section::Section;
+
+/// Conversions
+impl<'a> Item<'a> {
+    /// Converts the current inline table into a table.
+    ///
+    /// The comment after the inline table goes to the header of the table.
+    pub fn into_table(self) -> Result<Item<'a>> {
+        match self {
+            Item::InlineTable { val, trivia } => {
+                let mut table = Container::new();
+                for (k, mut v) in val.body {
+                    *v.trivia_mut() = Trivia {
+                        trail: ::NL,
+                        ..Trivia::default()
+                    };
+                    table.append(k, v)?;
+                }
+                Ok(Item::Table {
+                    is_aot_elem: false,
+                    val: table,
+                    trivia: keep_comment(trivia),
+                })
+            }
+            Item::Table { .. } => Ok(self),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Converts the current table into an inline table.
+    ///
+    /// Child tables become nested inline tables named after the last
+    /// segment of their header, and child AoTs become arrays of inline
+    /// tables. The comment of the header goes after the inline table.
+    pub fn into_inline_table(self) -> Result<Item<'a>> {
+        match self {
+            Item::Table { val, trivia, .. } => {
+                let mut table = Container::new();
+                for (k, v) in val.body {
+                    let k = match k {
+                        Some(k) => k,
+                        None => continue,
+                    };
+                    let (k, mut v) = match v {
+                        Item::Table { .. } => (segment_key(&k), v.into_inline_table()?),
+                        Item::AoT(_) => (segment_key(&k), v.into_array()?),
+                        v => (k, v),
+                    };
+                    *v.trivia_mut() = Trivia::default();
+                    table.append(k, v)?;
+                }
+                Ok(Item::InlineTable {
+                    val: table,
+                    trivia: keep_comment(trivia),
+                })
+            }
+            Item::InlineTable { .. } => Ok(self),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Converts the current array of inline tables into an AoT.
+    pub fn into_aot(self) -> Result<Item<'a>> {
+        match self {
+            Item::Array { val, trivia } => {
+                let mut tables = Vec::new();
+                for v in val {
+                    match v {
+                        Item::InlineTable { .. } => tables.push(v.into_table()?),
+                        Item::WS(_) | Item::Comment(_) => {}
+                        _ => bail!(ErrorKind::APIWrongItem),
+                    }
+                }
+                if let Some(&mut Item::Table { trivia: ref mut first, .. }) = tables.first_mut() {
+                    *first = keep_comment(trivia);
+                }
+                let mut aot = Item::AoT(Vec::with_capacity(tables.len()));
+                for table in tables {
+                    aot.push(table)?;
+                }
+                Ok(aot)
+            }
+            Item::AoT(_) => Ok(self),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+
+    /// Converts the current AoT into a single-line array of inline tables.
+    pub fn into_array(self) -> Result<Item<'a>> {
+        match self {
+            Item::AoT(tables) => {
+                let trivia = match tables.first() {
+                    Some(table) => keep_comment(table.trivia().clone()),
+                    None => Trivia::new(),
+                };
+                let mut array = Item::Array {
+                    val: Vec::with_capacity(tables.len()),
+                    trivia,
+                };
+                for table in tables {
+                    array.push(table.into_inline_table()?)?;
+                }
+                Ok(array)
+            }
+            Item::Array { .. } => Ok(self),
+            _ => bail!(ErrorKind::APIWrongItem),
+        }
+    }
+}
+
+impl<'a> Container<'a> {
+    /// Turns the inline table or array of inline tables at `path` into a
+    /// table or AoT section.
+    ///
+    /// `path` holds the names of the tables leading to the key, followed by
+    /// the key itself; `["dependencies", "serde"]` turns the `serde` inline
+    /// table of `[dependencies]` into a `[dependencies.serde]` section. The
+    /// comments above the key/value go above the header.
+    pub fn explode(&mut self, path: &[&str]) -> Result<()> {
+        let (name, parents) = match path.split_last() {
+            Some(split) => split,
+            None => bail!(ErrorKind::InvalidPosition),
+        };
+        let (parent, prefix) = self.table_at(parents)?;
+        let key = Key::for_name(name.to_string());
+        let pos = match parent.map.get(&key) {
+            Some(&pos) => pos,
+            None => bail!(ErrorKind::NonExistentKey(key.as_string())),
+        };
+        let item = match parent.body[pos].1 {
+            Item::InlineTable { .. } => parent.body[pos].1.clone().into_table()?,
+            Item::Array { .. } => parent.body[pos].1.clone().into_aot()?,
+            _ => bail!(ErrorKind::APIWrongItem),
+        };
+        let header = Key {
+            t: KeyType::Bare,
+            sep: "",
+            key: header_name(&prefix, name).into(),
+        };
+        if parent.map.get(&header).is_some_and(|&p| p != pos) {
+            bail!(ErrorKind::DuplicateKey(header.key.to_string()));
+        }
+
+        let start = parent.leading_start(pos);
+        let comments = parent.body
+            .drain(start..pos + 1)
+            .map(|entry| entry.1)
+            .filter(|item| item.is_comment())
+            .collect();
+        parent.rebuild_map();
+        parent.insert_section(Section {
+            key: header,
+            item,
+            comments,
+            gap: vec![::NL],
+        })
+    }
+
+    /// Turns the table or AoT at `path` into an inline table or array of
+    /// inline tables, placed after the last key/value of its parent.
+    ///
+    /// The comments above the header go above the key/value.
+    pub fn collapse(&mut self, path: &[&str]) -> Result<()> {
+        let (name, parents) = match path.split_last() {
+            Some(split) => split,
+            None => bail!(ErrorKind::InvalidPosition),
+        };
+        let (parent, prefix) = self.table_at(parents)?;
+        let header = Key::new(header_name(&prefix, name));
+        let key = Key::for_name(name.to_string());
+        if key != header && parent.map.contains_key(&key) {
+            bail!(ErrorKind::DuplicateKey(key.key.to_string()));
+        }
+
+        let section = parent.take_section(&header)?;
+        let item = match section.item {
+            Item::AoT(_) => section.item.into_array()?,
+            _ => section.item.into_inline_table()?,
+        };
+        parent.insert(key.clone(), item)?;
+        for comment in section.comments.into_iter().rev() {
+            parent.insert_before(&key, None, comment)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the body of the table at `path`, and its header name.
+    fn table_at(&mut self, path: &[&str]) -> Result<(&mut Container<'a>, String)> {
+        let mut container = self;
+        let mut name = String::new();
+        for segment in path {
+            name = header_name(&name, segment);
+            let key = Key::new(name.clone());
+            let pos = match container.map.get(&key) {
+                Some(&pos) => pos,
+                None => bail!(ErrorKind::NonExistentKey(key.as_string())),
+            };
+            container = match container.body[pos].1 {
+                Item::Table { ref mut val, .. } => val,
+                _ => bail!(ErrorKind::APIWrongItem),
+            };
+        }
+        Ok((container, name))
+    }
+}
+
+/// Returns the header name of the table `name` nested in the table `prefix`.
+fn header_name(prefix: &str, name: &str) -> String {
+    let name = Key::for_name(name).as_string();
+    if prefix.is_empty() {
+        name
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+/// Returns the key of the last segment of the header name of `key`.
+fn segment_key<'a>(key: &Key<'a>) -> Key<'a> {
+    let (start, end) = last_segment(&key.key);
+    let segment = &key.key[start..end];
+    let (t, inner) = if segment.starts_with('"') && segment.len() > 1 {
+        (KeyType::Basic, &segment[1..segment.len() - 1])
+    } else if segment.starts_with('\'') && segment.len() > 1 {
+        (KeyType::Literal, &segment[1..segment.len() - 1])
+    } else {
+        (KeyType::Bare, segment)
+    };
+    Key {
+        t,
+        sep: " = ",
+        key: inner.to_string().into(),
+    }
+}
+
+/// Returns new trivia that keeps the comment of `trivia`, to carry the
+/// comment of a value over to a header or the other way round.
+fn keep_comment(trivia: Trivia) -> Trivia {
+    Trivia {
+        comment_ws: trivia.comment_ws,
+        comment: trivia.comment,
+        ..Trivia::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+    use items::*;
+    use api::*;
+
+    #[test]
+    fn into_table() {
+        let src = "serde = {version = \"1\", features = [\"derive\"]} # why\n";
+        let (_, v) = key_value(src).unwrap();
+        let mut doc = Parser::new("").parse().unwrap();
+        doc.insert(Key::new("serde"), v.into_table().unwrap()).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            format!("[serde] # why{nl}version = \"1\"{nl}features = [\"derive\"]{nl}", nl = ::NL)
+        );
+        assert!(integer("1").unwrap().into_table().is_err());
+    }
+
+    #[test]
+    fn into_inline_table() {
+        let src = "[t] # note\n# about a\na = 1 # one\n\nb = \"x\"\n[t.c]\nd = true\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let (_, t) = doc.remove(&Key::new("t")).unwrap();
+        let t = t.into_inline_table().unwrap();
+        assert_eq!(t.as_string(), "{a = 1, b = \"x\", c = {d = true}}");
+        assert_eq!(t.trivia().comment, "# note");
+    }
+
+    #[test]
+    fn array_and_aot() {
+        let src = "[[bin]]\nname = \"a\"\n\n[[bin]]\nname = \"b\"\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let (_, bins) = doc.remove(&Key::new("bin")).unwrap();
+        let array = bins.into_array().unwrap();
+        assert_eq!(array.as_string(), "[{name = \"a\"}, {name = \"b\"}]");
+        let aot = array.into_aot().unwrap();
+        assert!(aot.is_aot());
+        assert!(aot[1]["name"].is_string());
+        assert!(value("[1, 2]").unwrap().into_aot().is_err());
+    }
+
+    #[test]
+    fn explode() {
+        let src = "[dependencies]\n# JSON\nserde = {version = \"1\", features = [\"derive\"]}\n\
+                   log = \"0.4\"\n\n[dev-dependencies]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.explode(&["dependencies", "serde"]).unwrap();
+        let expected = format!(
+            "[dependencies]\nlog = \"0.4\"\n\n# JSON\n[dependencies.serde]{nl}\
+             version = \"1\"{nl}features = [\"derive\"]{nl}\n[dev-dependencies]\n",
+            nl = ::NL
+        );
+        assert_eq!(doc.as_string(), expected);
+        let reparsed = Parser::new(&expected).parse().unwrap();
+        assert_eq!(reparsed.as_string(), expected);
+
+        assert!(doc.explode(&["dependencies", "log"]).is_err());
+        assert!(doc.explode(&["dependencies", "rand"]).is_err());
+        assert!(doc.explode(&[]).is_err());
+    }
+
+    #[test]
+    fn collapse() {
+        let src = "[dependencies]\nlog = \"0.4\"\n\n# JSON\n[dependencies.serde] # pinned\n\
+                   version = \"1\"\n\n[dev-dependencies]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.collapse(&["dependencies", "serde"]).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            format!(
+                "[dependencies]\nlog = \"0.4\"\n# JSON{nl}serde = {{version = \"1\"}} # pinned{nl}\
+                 \n[dev-dependencies]\n",
+                nl = ::NL
+            )
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let src = "a = 1\n[[points]]\nx = 1\n[[points]]\nx = 2\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.collapse(&["points"]).unwrap();
+        assert_eq!(doc.as_string(), format!("a = 1\npoints = [{{x = 1}}, {{x = 2}}]{}", ::NL));
+        doc.explode(&["points"]).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            format!("a = 1\n\n[[points]]{nl}x = 1{nl}[[points]]{nl}x = 2{nl}", nl = ::NL)
+        );
+    }
+}
@@ -0,0 +1,499 @@
+//! Deserializing Rust types from a parsed document, with serde.
+//!
+//! This module is available with the `serde` feature. Strings are given
+//! with their escapes decoded, and datetimes as their RFC 3339 text. Enums
+//! are read from a string for unit variants, or from a table with a single
+//! key naming the variant.
+//!
+//! Table headers are stored under their full dotted name, so the entries of
+//! each table are regrouped by segment first: `[a.b]` is read as the table
+//! `b` inside the table `a`, whether or not `[a]` appears in the document.
+//!
+//! The byte ranges of the values, for `spanned::Spanned`, are only known
+//! when reading the text with `from_str`.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate Molten;
+//!
+//! #[derive(Deserialize)]
+//! struct Package {
+//!     name: String,
+//!     version: String,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Manifest {
+//!     package: Package,
+//! }
+//!
+//! # fn main() {
+//! let src = "[package]\nname = \"molten\"\nversion = '0.1.0'\n";
+//! let manifest: Manifest = Molten::de::from_str(src).unwrap();
+//! assert_eq!(manifest.package.name, "molten");
+//! # }
+//! ```
+
+use std::fmt::Display;
+use std::vec;
+
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::de::value::{StrDeserializer, UsizeDeserializer};
+
+use items::*;
+use errors::*;
+use container::Container;
+use parser::Parser;
+use spanned;
+use tomldoc::TOMLDocument;
+use tree::{entries, item_node, Node};
+
+/// Deserializes a `T` from the text of a TOML document.
+pub fn from_str<T: DeserializeOwned>(src: &str) -> Result<T> {
+    let doc = Parser::new(src).parse()?;
+    T::deserialize(Node::Table(entries(&doc, 0, Some(src)), Some((0, src.len()))))
+}
+
+/// Deserializes a `T` from a document.
+pub fn from_document<'de, T: Deserialize<'de>>(doc: &TOMLDocument) -> Result<T> {
+    T::deserialize(doc)
+}
+
+/// Deserializes a `T` from an item.
+pub fn from_item<'de, T: Deserialize<'de>>(item: &Item) -> Result<T> {
+    T::deserialize(item)
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        ErrorKind::Serde(String::new(), msg.to_string()).into()
+    }
+}
+
+/// Adds `segment` in front of the path of a serde error.
+pub(crate) fn within(e: Error, segment: &str) -> Error {
+    let (path, msg) = match *e.kind() {
+        ErrorKind::Serde(ref path, ref msg) => (path.clone(), msg.clone()),
+        _ => return e,
+    };
+    let path = if path.is_empty() {
+        segment.to_string()
+    } else if path.starts_with('[') {
+        format!("{}{}", segment, path)
+    } else {
+        format!("{}.{}", segment, path)
+    };
+    ErrorKind::Serde(path, msg).into()
+}
+
+/// Returns the name as written in a path: bare if it can be.
+fn path_segment(name: &str) -> String {
+    Key::for_name(name).as_string()
+}
+
+impl<'de, 'b, 'a> de::Deserializer<'de> for &'b Container<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        Node::Table(entries(self, 0, None), None).deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        Node::Table(entries(self, 0, None), None).deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        Node::Table(entries(self, 0, None), None).deserialize_struct(name, fields, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map identifier ignored_any
+    }
+}
+
+impl<'de, 'b, 'a> de::Deserializer<'de> for &'b Item<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        item_node(self).deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        item_node(self).deserialize_option(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        item_node(self).deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        item_node(self).deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier
+        ignored_any
+    }
+}
+
+impl<'de, 'b, 'a> de::Deserializer<'de> for Node<'b, 'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Node::Table(entries, _) => visitor.visit_map(MapAccess {
+                entries: entries.into_iter(),
+                value: None,
+            }),
+            Node::Array(items, _) => visitor.visit_seq(SeqAccess {
+                items: items.into_iter().enumerate(),
+            }),
+            Node::Value(item, _) => match *item {
+                Item::Integer { val, .. } => visitor.visit_i64(val),
+                Item::Float { val, .. } => visitor.visit_f64(val),
+                Item::Bool { val, .. } => visitor.visit_bool(val),
+                Item::DateTime { raw, .. } => visitor.visit_str(raw),
+                Item::Str { .. } => visitor.visit_str(&item.as_str()?),
+                _ => visitor.visit_unit(),
+            },
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Node::Value(item, _) if item.is_string() => {
+                visitor.visit_enum(item.as_str()?.into_owned().into_deserializer())
+            }
+            Node::Table(mut entries, _) => {
+                if entries.len() != 1 {
+                    return Err(de::Error::custom(
+                        "expected a table with a single key naming the enum variant",
+                    ));
+                }
+                let (variant, node) = entries.pop().unwrap();
+                visitor.visit_enum(EnumAccess { variant, node })
+            }
+            _ => Err(de::Error::custom("expected a string or a table for an enum")),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        if name != spanned::NAME || fields != spanned::FIELDS {
+            return self.deserialize_any(visitor);
+        }
+        match self.span() {
+            Some(span) => visitor.visit_map(SpannedAccess {
+                span,
+                node: Some(self),
+                field: 0,
+            }),
+            None => Err(de::Error::custom(
+                "the value has no span: it is either not read with `from_str`, \
+                 or a table without a header of its own",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier
+        ignored_any
+    }
+}
+
+/// Gives the entries of a table to a visitor.
+struct MapAccess<'b, 'a: 'b> {
+    entries: vec::IntoIter<(String, Node<'b, 'a>)>,
+    value: Option<(String, Node<'b, 'a>)>,
+}
+
+impl<'de, 'b, 'a> de::MapAccess<'de> for MapAccess<'b, 'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.entries.next() {
+            Some((name, node)) => {
+                let key = seed.deserialize(name.as_str().into_deserializer());
+                self.value = Some((name, node));
+                key.map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (name, node) = match self.value.take() {
+            Some(value) => value,
+            None => return Err(de::Error::custom("value requested before its key")),
+        };
+        seed.deserialize(node).map_err(|e| within(e, &path_segment(&name)))
+    }
+}
+
+/// Gives the span and the value of a node to the visitor of a `Spanned`.
+struct SpannedAccess<'b, 'a: 'b> {
+    span: (usize, usize),
+    node: Option<Node<'b, 'a>>,
+    field: usize,
+}
+
+impl<'de, 'b, 'a> de::MapAccess<'de> for SpannedAccess<'b, 'a> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match spanned::FIELDS.get(self.field) {
+            Some(&name) => {
+                self.field += 1;
+                let name: StrDeserializer<Error> = name.into_deserializer();
+                seed.deserialize(name).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let offset = match self.field {
+            1 => self.span.0,
+            2 => self.span.1,
+            _ => match self.node.take() {
+                Some(node) => return seed.deserialize(node),
+                None => return Err(de::Error::custom("value requested before its key")),
+            },
+        };
+        let offset: UsizeDeserializer<Error> = offset.into_deserializer();
+        seed.deserialize(offset)
+    }
+}
+
+/// Gives the items of an array to a visitor.
+struct SeqAccess<'b, 'a: 'b> {
+    items: ::std::iter::Enumerate<vec::IntoIter<Node<'b, 'a>>>,
+}
+
+impl<'de, 'b, 'a> de::SeqAccess<'de> for SeqAccess<'b, 'a> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.items.next() {
+            Some((idx, node)) => seed
+                .deserialize(node)
+                .map(Some)
+                .map_err(|e| within(e, &format!("[{}]", idx))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Gives an enum variant read from a single-key table to a visitor.
+struct EnumAccess<'b, 'a: 'b> {
+    variant: String,
+    node: Node<'b, 'a>,
+}
+
+impl<'de, 'b, 'a> de::EnumAccess<'de> for EnumAccess<'b, 'a> {
+    type Error = Error;
+    type Variant = VariantAccess<'b, 'a>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let name: StrDeserializer<Error> = self.variant.as_str().into_deserializer();
+        let variant = seed.deserialize(name)?;
+        let access = VariantAccess {
+            name: self.variant,
+            node: self.node,
+        };
+        Ok((variant, access))
+    }
+}
+
+/// Gives the contents of an enum variant to a visitor.
+struct VariantAccess<'b, 'a: 'b> {
+    name: String,
+    node: Node<'b, 'a>,
+}
+
+impl<'de, 'b, 'a> de::VariantAccess<'de> for VariantAccess<'b, 'a> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.node {
+            Node::Table(ref entries, _) if entries.is_empty() => Ok(()),
+            _ => Err(within(de::Error::custom("expected an empty table"), &self.name)),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        let name = path_segment(&self.name);
+        seed.deserialize(self.node).map_err(|e| within(e, &name))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        let name = path_segment(&self.name);
+        de::Deserializer::deserialize_seq(self.node, visitor).map_err(|e| within(e, &name))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let name = path_segment(&self.name);
+        de::Deserializer::deserialize_map(self.node, visitor).map_err(|e| within(e, &name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use parser::Parser;
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Level {
+        Low,
+        High,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Source {
+        Path(String),
+        Git { url: String, rev: Option<String> },
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Dependency {
+        name: String,
+        source: Source,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Config {
+        title: String,
+        level: Level,
+        ports: Vec<u16>,
+        released: String,
+        server: BTreeMap<String, BTreeMap<String, i64>>,
+        dependency: Vec<Dependency>,
+        missing: Option<bool>,
+    }
+
+    #[test]
+    fn deserialize() {
+        let src = "title = \"caf\\u00E9\\tok\" # note\nlevel = 'high'\n\
+                   ports = [8080, 8081]\nreleased = 1979-05-27T07:32:00Z\n\
+                   [server.alpha]\nmax = 3\n\n[server.beta]\nmax = 5\n\
+                   [[dependency]]\nname = \"a\"\nsource = {Path = \"../a\"}\n\
+                   [[dependency]]\nname = \"b\"\n\
+                   [dependency.source.Git]\nurl = '''\nhttps://x'''\n";
+        let config: Config = from_str(src).unwrap();
+        assert_eq!(config.title, "caf\u{e9}\tok");
+        assert_eq!(config.level, Level::High);
+        assert_eq!(config.ports, vec![8080, 8081]);
+        assert_eq!(config.released, "1979-05-27T07:32:00Z");
+        assert_eq!(config.server["alpha"]["max"], 3);
+        assert_eq!(config.server["beta"]["max"], 5);
+        assert_eq!(config.dependency[0].source, Source::Path("../a".to_string()));
+        assert_eq!(
+            config.dependency[1].source,
+            Source::Git {
+                url: "https://x".to_string(),
+                rev: None,
+            }
+        );
+        assert_eq!(config.missing, None);
+    }
+
+    #[test]
+    fn deserialize_item() {
+        let doc = Parser::new("[a]\nx = 1\n[a.b]\ny = 2\n").parse().unwrap();
+        let b: BTreeMap<String, i64> = from_item(&doc["a"]["a.b"]).unwrap();
+        assert_eq!(b["y"], 2);
+
+        #[derive(Deserialize)]
+        struct A {
+            x: i64,
+            b: BTreeMap<String, i64>,
+        }
+        let a: A = from_item(&doc["a"]).unwrap();
+        assert_eq!((a.x, a.b["y"]), (1, 2));
+    }
+
+    #[test]
+    fn error_path() {
+        #[derive(Debug, Deserialize)]
+        struct Port {
+            #[allow(dead_code)]
+            port: u16,
+        }
+        #[derive(Debug, Deserialize)]
+        struct Servers {
+            #[allow(dead_code)]
+            servers: Vec<Port>,
+        }
+        let src = "[[servers]]\nport = 80\n[[servers]]\nport = \"http\"\n";
+        let err = from_str::<Servers>(src).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Serde(ref path, _) => assert_eq!(path, "servers[1].port"),
+            _ => panic!("unexpected error: {}", err),
+        }
+        assert!(err.to_string().ends_with("at `servers[1].port`"));
+
+        let err = from_str::<Servers>("[[servers]]\n").unwrap_err();
+        assert_eq!(err.to_string(), "missing field `port` at `servers[0]`");
+    }
+}
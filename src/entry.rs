@@ -0,0 +1,252 @@
+//! `HashMap`-style entries of a `Container`.
+//!
+//! New items are placed with `Container::insert`, so a value lands after the
+//! last key/value of its table and a table at the end of its container.
+
+use std::mem;
+
+use items::*;
+use errors::*;
+use container::Container;
+
+impl<'a> Container<'a> {
+    /// Returns the entry for `key`, for in-place manipulation.
+    pub fn entry(&mut self, key: Key<'a>) -> Entry<'_, 'a> {
+        match self.map.get(&key) {
+            Some(&idx) => Entry::Occupied(OccupiedEntry {
+                container: self,
+                idx,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                container: self,
+                key,
+            }),
+        }
+    }
+}
+
+/// A view into a single entry of a container, which may either be vacant
+/// or occupied.
+#[derive(Debug)]
+pub enum Entry<'c, 'a: 'c> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'c, 'a>),
+    /// A vacant entry.
+    Vacant(VacantEntry<'c, 'a>),
+}
+
+impl<'c, 'a: 'c> Entry<'c, 'a> {
+    /// Returns the key of this entry.
+    pub fn key(&self) -> &Key<'a> {
+        match *self {
+            Entry::Occupied(ref e) => e.key(),
+            Entry::Vacant(ref e) => e.key(),
+        }
+    }
+
+    /// Inserts `default` if the entry is vacant, and returns a mutable
+    /// reference to the item of the entry.
+    pub fn or_insert(self, default: Item<'a>) -> Result<&'c mut Item<'a>> {
+        match self {
+            Entry::Occupied(e) => Ok(e.into_mut()),
+            Entry::Vacant(e) => e.insert(default),
+        }
+    }
+
+    /// Inserts the result of `default` if the entry is vacant, and returns
+    /// a mutable reference to the item of the entry.
+    pub fn or_insert_with<F: FnOnce() -> Item<'a>>(self, default: F) -> Result<&'c mut Item<'a>> {
+        match self {
+            Entry::Occupied(e) => Ok(e.into_mut()),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    /// Inserts an empty table if the entry is vacant, and returns the
+    /// container of the table of the entry.
+    ///
+    /// As with the tables produced by the parser, a table nested in another
+    /// one is keyed by its full dotted name; within an inline table, use
+    /// `or_insert` with an inline table instead. Fails if the entry holds
+    /// something other than a table or an inline table.
+    pub fn or_insert_table(self) -> Result<&'c mut Container<'a>> {
+        let item = self.or_insert_with(|| Item::Table {
+            is_aot_elem: false,
+            val: Container::new(),
+            trivia: Trivia::new(),
+        })?;
+        item.as_table_mut()
+    }
+
+    /// Calls `f` on the item of the entry if it is occupied.
+    pub fn and_modify<F: FnOnce(&mut Item<'a>)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+}
+
+/// A view into an occupied entry of a container.
+#[derive(Debug)]
+pub struct OccupiedEntry<'c, 'a: 'c> {
+    container: &'c mut Container<'a>,
+    idx: usize,
+}
+
+impl<'c, 'a: 'c> OccupiedEntry<'c, 'a> {
+    /// Returns the key of this entry.
+    pub fn key(&self) -> &Key<'a> {
+        self.container.body[self.idx].0.as_ref().unwrap()
+    }
+
+    /// Returns a reference to the item of this entry.
+    pub fn get(&self) -> &Item<'a> {
+        &self.container.body[self.idx].1
+    }
+
+    /// Returns a mutable reference to the item of this entry.
+    pub fn get_mut(&mut self) -> &mut Item<'a> {
+        &mut self.container.body[self.idx].1
+    }
+
+    /// Converts the entry into a mutable reference to its item.
+    pub fn into_mut(self) -> &'c mut Item<'a> {
+        &mut self.container.body[self.idx].1
+    }
+
+    /// Replaces the item of this entry, and returns the old one.
+    pub fn insert(&mut self, item: Item<'a>) -> Item<'a> {
+        mem::replace(self.get_mut(), item)
+    }
+
+    /// Replaces the value of this entry and returns the old one, keeping
+    /// the decoration of the old value; see `Item::set_value`.
+    pub fn set_value(&mut self, value: Item<'a>) -> Result<Item<'a>> {
+        self.get_mut().set_value(value)
+    }
+
+    /// Removes the entry from the container and returns its item.
+    pub fn remove(self) -> Item<'a> {
+        let key = self.key().clone();
+        self.container.remove(&key).unwrap().1
+    }
+}
+
+/// A view into a vacant entry of a container.
+#[derive(Debug)]
+pub struct VacantEntry<'c, 'a: 'c> {
+    container: &'c mut Container<'a>,
+    key: Key<'a>,
+}
+
+impl<'c, 'a: 'c> VacantEntry<'c, 'a> {
+    /// Returns the key of this entry.
+    pub fn key(&self) -> &Key<'a> {
+        &self.key
+    }
+
+    /// Inserts `item` in the container under the key of this entry, and
+    /// returns a mutable reference to it.
+    pub fn insert(self, item: Item<'a>) -> Result<&'c mut Item<'a>> {
+        let VacantEntry { container, key } = self;
+        container.insert(key.clone(), item)?;
+        let idx = container.map[&key];
+        Ok(&mut container.body[idx].1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::Parser;
+    use api::*;
+
+    #[test]
+    fn or_insert() {
+        let src = "[dependencies]\nserde = \"1\"\n\n[dev-dependencies]\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        {
+            let deps = doc.entry(Key::new("dependencies")).or_insert_table().unwrap();
+            deps.entry(Key::new("log")).or_insert(value("\"0.4\"").unwrap()).unwrap();
+            let serde = deps.entry(Key::new("serde"))
+                .or_insert(value("\"2\"").unwrap())
+                .unwrap();
+            assert_eq!(serde.as_string(), "\"1\"");
+        }
+        assert_eq!(
+            doc.as_string(),
+            format!(
+                "[dependencies]\nserde = \"1\"\nlog = \"0.4\"{}\n[dev-dependencies]\n",
+                ::NL
+            )
+        );
+    }
+
+    #[test]
+    fn or_insert_table() {
+        let src = "[package]\nname = \"x\"\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.entry(Key::new("features"))
+            .or_insert_table()
+            .unwrap()
+            .entry(Key::new("default"))
+            .or_insert_with(|| array().unwrap())
+            .unwrap();
+        assert_eq!(
+            doc.as_string(),
+            format!("[package]\nname = \"x\"\n[features]{nl}default = []{nl}", nl = ::NL)
+        );
+        assert!(doc.entry(Key::new("features")).or_insert_table().is_ok());
+        assert!(
+            doc.entry(Key::new("package"))
+                .or_insert_table()
+                .unwrap()
+                .entry(Key::new("name"))
+                .or_insert_table()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn inline_table() {
+        let src = "serde = {version = \"1\"}";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.entry(Key::new("serde"))
+            .or_insert_table()
+            .unwrap()
+            .entry(Key::new("optional"))
+            .or_insert(value("true").unwrap())
+            .unwrap();
+        assert_eq!(doc.as_string(), "serde = {version = \"1\", optional = true}");
+    }
+
+    #[test]
+    fn and_modify() {
+        let mut doc = Parser::new("a = 1\n").parse().unwrap();
+        let mut calls = 0;
+        doc.entry(Key::new("a"))
+            .and_modify(|_| calls += 1)
+            .or_insert(integer("2").unwrap())
+            .unwrap();
+        doc.entry(Key::new("b"))
+            .and_modify(|_| calls += 1)
+            .or_insert(integer("2").unwrap())
+            .unwrap();
+        assert_eq!(calls, 1);
+        assert_eq!(doc.as_string(), format!("a = 1\nb = 2{}", ::NL));
+    }
+
+    #[test]
+    fn occupied() {
+        let mut doc = Parser::new("a = 1\nb = 2\n").parse().unwrap();
+        match doc.entry(Key::new("a")) {
+            Entry::Occupied(e) => assert!(e.remove().is_integer()),
+            Entry::Vacant(_) => panic!("Entry should be occupied"),
+        }
+        assert_eq!(doc.as_string(), "b = 2\n");
+    }
+}
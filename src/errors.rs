@@ -74,9 +74,51 @@ error_chain! {
             display("Key \"{}\"does not exist.", key)
         }
 
+        /// An index past the end of an array was used.
+        IndexOutOfBounds(idx: usize) {
+            description("Index out of bounds")
+            display("Index {} is out of bounds.", idx)
+        }
+
+        /// An item was placed where it would change the structure of the
+        /// document: a value after a table, or a table before a value.
+        InvalidPosition {
+            description("Values must come before tables within a container.")
+        }
+
+        /// A comment did not start with `#`, or spanned several lines.
+        InvalidComment(comment: String) {
+            description("Invalid comment")
+            display("Invalid comment: {:?}", comment)
+        }
+
+        /// A value could not be converted to or from a Rust type. The path
+        /// leads to the key where it happened.
+        Serde(path: String, msg: String) {
+            description("Serde error")
+            display("{}", at_path(msg, path))
+        }
+
+        /// A document could not be converted to or from JSON. The path
+        /// leads to the key where it happened.
+        Json(path: String, msg: String) {
+            description("JSON conversion error")
+            display("{}", at_path(msg, path))
+        }
+
         /// A string was found that did not match required string syntax.
         ParseStringError {
             description("Input does not contain a valid string.")
         }
     }
 }
+
+/// Returns `msg`, followed by the path that leads to where it happened if
+/// there is one.
+fn at_path(msg: &str, path: &str) -> String {
+    if path.is_empty() {
+        msg.to_string()
+    } else {
+        format!("{} at `{}`", msg, path)
+    }
+}
@@ -0,0 +1,722 @@
+//! Canonical formatting of documents.
+//!
+//! Unlike `as_string`, which gives back the text a document was parsed from,
+//! `format` lays the document out anew, as set by `FormatOptions`. Only the
+//! layout changes: the values are written as in the source, and the
+//! comments are kept, each in the same place relative to the entries.
+//!
+//! ```
+//! use Molten::format::{format, FormatOptions};
+//! use Molten::parser::Parser;
+//!
+//! let src = "# Server\n[server]\n\n\nhost='x'   # main\nports=[ 80,443 ]\n[client]\nid=1\n";
+//! let doc = Parser::new(src).parse().unwrap();
+//! let options = FormatOptions {
+//!     align_entries: true,
+//!     ..FormatOptions::default()
+//! };
+//! assert_eq!(
+//!     format(&doc, &options),
+//!     "# Server\n[server]\nhost  = 'x' # main\nports = [80, 443]\n\n[client]\nid = 1\n"
+//!         .replace('\n', Molten::NL)
+//! );
+//! ```
+//!
+//! `format_range` formats only the lines in a byte range, as for the "format
+//! selection" of an editor, and returns the changes as a list of edits.
+
+use std::ops::Range;
+
+use items::*;
+use errors::*;
+use container::Container;
+use parser::Parser;
+use tomldoc::TOMLDocument;
+
+/// Options for `format`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Indent the headers of nested tables, along with their entries, by
+    /// one level per segment of their name after the first.
+    pub indent_tables: bool,
+    /// The text of one level of indentation.
+    pub indent_string: String,
+    /// Align the `=` of the consecutive key/values of a block.
+    pub align_entries: bool,
+    /// Write `=` with one space on each side, rather than none.
+    pub space_around_eq: bool,
+    /// Write one-line arrays with a space inside the brackets, as in
+    /// `[ 1, 2 ]`.
+    pub array_spacing: bool,
+    /// Write inline tables with a space inside the braces, as in
+    /// `{ x = 1 }`.
+    pub inline_table_spacing: bool,
+    /// The number of blank lines before a table header, and the comments
+    /// right above it.
+    pub blank_lines_between_tables: usize,
+    /// End the last element of a multi-line array with a comma.
+    pub trailing_comma: bool,
+    /// The width of the lines past which an array is written one element
+    /// per line.
+    pub column_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> FormatOptions {
+        FormatOptions {
+            indent_tables: false,
+            indent_string: "  ".to_string(),
+            align_entries: false,
+            space_around_eq: true,
+            array_spacing: false,
+            inline_table_spacing: false,
+            blank_lines_between_tables: 1,
+            trailing_comma: true,
+            column_width: 80,
+        }
+    }
+}
+
+/// Returns the text of `doc`, laid out as set by `options`.
+///
+/// Blank lines are kept between key/values, though no more than one in a
+/// row, and dropped at the start and end of a table.
+pub fn format(doc: &TOMLDocument, options: &FormatOptions) -> String {
+    let mut lines = Vec::new();
+    flatten(doc, 0, options, &mut lines);
+
+    let mut formatter = Formatter {
+        options,
+        out: String::new(),
+        blanks: 0,
+        at_start: true,
+        width: 0,
+    };
+    for idx in 0..lines.len() {
+        formatter.line(&lines, idx);
+    }
+    formatter.out
+}
+
+/// A change to a text: the bytes in `range` are replaced by `new_text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The byte range of the text to replace.
+    pub range: Range<usize>,
+    /// The text to put in its place.
+    pub new_text: String,
+}
+
+/// Returns the edits that format the lines of `src` in the byte range
+/// `range`, and leave the other lines as they are. An empty range stands
+/// for the line it is on, as for formatting on typing.
+///
+/// The lines are laid out as by `format` on the whole document. Each edit
+/// spans as little text as it can, and they come in order, without
+/// overlapping, so they can be applied as the `TextEdit`s of the Language
+/// Server Protocol. Their ranges are byte offsets into `src`: the caller
+/// converts them into positions, whose characters the protocol counts in
+/// UTF-16 code units by default.
+pub fn format_range(
+    src: &str,
+    range: Range<usize>,
+    options: &FormatOptions,
+) -> Result<Vec<TextEdit>> {
+    let doc = Parser::new(src).parse()?;
+    let formatted = format(&doc, options);
+    let old: Vec<&str> = src.split_inclusive('\n').collect();
+    let new: Vec<&str> = formatted.split_inclusive('\n').collect();
+    let mut starts: Vec<usize> = old.iter()
+        .scan(0, |pos, line| {
+            let start = *pos;
+            *pos += line.len();
+            Some(start)
+        })
+        .collect();
+    starts.push(src.len());
+
+    let selected = |line: usize| {
+        let (start, end) = (starts[line], starts[line + 1]);
+        if range.start == range.end {
+            start <= range.start && (range.start < end || end == src.len())
+        } else {
+            start < range.end && range.start < end
+        }
+    };
+    let mut edits = Vec::new();
+    for (lines, new_lines) in hunks(&old, &new) {
+        // Lines are inserted along with the line they go before.
+        let covered = if lines.start == lines.end {
+            old.is_empty() || selected(lines.start.min(old.len() - 1))
+        } else {
+            lines.clone().all(selected)
+        };
+        if covered {
+            let range = starts[lines.start]..starts[lines.end];
+            edits.extend(edit(src, range, &new[new_lines].concat()));
+        }
+    }
+    Ok(edits)
+}
+
+/// Returns the differences between the lines `old` and `new`, each as the
+/// range of the lines of `old` to replace with the range of those of `new`.
+///
+/// Lines that only differ in whitespace and in a trailing comma are paired,
+/// so that a line that is laid out anew makes a difference of its own.
+fn hunks(old: &[&str], new: &[&str]) -> Vec<(Range<usize>, Range<usize>)> {
+    let norm = |line: &&str| {
+        let text: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+        text.trim_end_matches(',').to_string()
+    };
+    let a: Vec<_> = old.iter().map(norm).collect();
+    let b: Vec<_> = new.iter().map(norm).collect();
+
+    let mut hunks = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (x, y) in common_lines(&a, &b) {
+        if (i, j) != (x, y) {
+            hunks.push((i..x, j..y));
+        }
+        if old[x] != new[y] {
+            hunks.push((x..x + 1, y..y + 1));
+        }
+        i = x + 1;
+        j = y + 1;
+    }
+    if (i, j) != (old.len(), new.len()) {
+        hunks.push((i..old.len(), j..new.len()));
+    }
+    hunks
+}
+
+/// Returns the indices of the elements of `a` and `b` that are kept by a
+/// shortest edit script from `a` to `b`, found with Myers' algorithm.
+fn common_lines<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    let idx = |k: isize| (k + max + 1) as usize;
+    // The furthest `x` reached on each diagonal `k = x - y`, and its values
+    // in the window `-d..=d` at the start of each round `d`.
+    let mut v = vec![0; 2 * max as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    'search: for d in 0..=max {
+        trace.push(v[idx(-d)..=idx(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[idx(k - 1)] < v[idx(k + 1)]) {
+                v[idx(k + 1)]
+            } else {
+                v[idx(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d) as usize];
+        let k = x - y;
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+            (at(prev_k), at(prev_k) - prev_k)
+        };
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            pairs.push((x as usize, y as usize));
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    pairs.reverse();
+    pairs
+}
+
+/// Returns the edit that replaces the text of `src` in `range` with `text`,
+/// with their common start and end left out, or `None` if they are equal.
+fn edit(src: &str, range: Range<usize>, text: &str) -> Option<TextEdit> {
+    let old = &src[range.clone()];
+    let prefix = old.char_indices()
+        .zip(text.chars())
+        .find(|&((_, a), b)| a != b)
+        .map_or(old.len().min(text.len()), |((i, _), _)| i);
+    let (old_rest, new_rest) = (&old[prefix..], &text[prefix..]);
+    let suffix = old_rest
+        .chars()
+        .rev()
+        .zip(new_rest.chars().rev())
+        .take_while(|&(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+    if old_rest.len() == suffix && new_rest.len() == suffix {
+        return None;
+    }
+    Some(TextEdit {
+        range: range.start + prefix..range.end - suffix,
+        new_text: new_rest[..new_rest.len() - suffix].to_string(),
+    })
+}
+
+/// A line of a document, as laid out by `format`.
+#[derive(Debug, Clone, Copy)]
+enum Line<'d> {
+    /// A blank line in the source.
+    Blank,
+    /// A comment line, at the indentation level `level`.
+    Comment { level: usize, text: &'d str },
+    /// A key/value.
+    Entry {
+        level: usize,
+        key: &'d Key<'d>,
+        item: &'d Item<'d>,
+    },
+    /// A table header, with the comment at its end.
+    Header {
+        level: usize,
+        key: &'d Key<'d>,
+        aot: bool,
+        comment: &'d str,
+    },
+}
+
+/// Adds the lines of `container`, whose entries are at the indentation
+/// level `level`, to `lines`.
+fn flatten<'d>(
+    container: &'d Container<'d>,
+    level: usize,
+    options: &FormatOptions,
+    lines: &mut Vec<Line<'d>>,
+) {
+    let depth = |key: &Key| {
+        if options.indent_tables {
+            key.segments().count().saturating_sub(1)
+        } else {
+            0
+        }
+    };
+    for (key, item) in &container.body {
+        match (key, item) {
+            (&None, &Item::WS(ws)) if ws.contains('\n') => lines.push(Line::Blank),
+            (&None, Item::Comment(trivia)) if trivia.comment.is_empty() => {
+                lines.push(Line::Blank)
+            }
+            (&None, Item::Comment(trivia)) => lines.push(Line::Comment {
+                level,
+                text: trivia.comment.trim_end(),
+            }),
+            (Some(key), Item::Table { val, trivia, .. }) => {
+                let level = depth(key);
+                lines.push(Line::Header {
+                    level,
+                    key,
+                    aot: false,
+                    comment: trivia.comment.trim_end(),
+                });
+                flatten(val, level, options, lines);
+            }
+            (Some(key), Item::AoT(tables)) => {
+                let level = depth(key);
+                for table in tables {
+                    if let Item::Table { val, trivia, .. } = table {
+                        lines.push(Line::Header {
+                            level,
+                            key,
+                            aot: true,
+                            comment: trivia.comment.trim_end(),
+                        });
+                        flatten(val, level, options, lines);
+                    }
+                }
+            }
+            (_, &Item::None) => {}
+            (Some(key), item) => lines.push(Line::Entry { level, key, item }),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the text of `key`, with no whitespace around its dots.
+fn key_text(key: &Key) -> String {
+    let segments: Vec<_> = key.segments()
+        .map(|s| {
+            let quote = match s.t {
+                KeyType::Bare => "",
+                KeyType::Basic => "\"",
+                KeyType::Literal => "'",
+            };
+            format!("{}{}{}", quote, s.raw, quote)
+        })
+        .collect();
+    segments.join(".")
+}
+
+/// Returns the index of the header that the comment at `idx` is right
+/// above, as part of a run of comment lines.
+fn header_below(lines: &[Line], idx: usize) -> Option<usize> {
+    let end = idx + lines[idx..]
+        .iter()
+        .take_while(|line| matches!(**line, Line::Comment { .. }))
+        .count();
+    match lines.get(end) {
+        Some(&Line::Header { .. }) => Some(end),
+        _ => None,
+    }
+}
+
+/// Writes the lines of a document.
+struct Formatter<'o> {
+    options: &'o FormatOptions,
+    out: String,
+    /// The number of blank lines to write before the next line.
+    blanks: usize,
+    /// `true` at the start of the document or of a table.
+    at_start: bool,
+    /// The width the keys of the current block are padded to.
+    width: usize,
+}
+
+impl<'o> Formatter<'o> {
+    /// Writes the line at `idx`.
+    fn line(&mut self, lines: &[Line], idx: usize) {
+        let prev_comment = idx > 0 && matches!(lines[idx - 1], Line::Comment { .. });
+        match lines[idx] {
+            Line::Blank => {
+                if !self.at_start {
+                    self.blanks = 1;
+                }
+            }
+            Line::Comment { level, text } => match header_below(lines, idx) {
+                // The comments right above a header go along with it.
+                Some(header) => {
+                    if !prev_comment {
+                        self.gap();
+                    }
+                    let level = match lines[header] {
+                        Line::Header { level, .. } => level,
+                        _ => level,
+                    };
+                    self.write(level, text);
+                }
+                None => {
+                    self.write(level, text);
+                    self.at_start = false;
+                }
+            },
+            Line::Entry { level, key, item } => {
+                let starts_block = idx == 0 || !matches!(lines[idx - 1], Line::Entry { .. });
+                if starts_block || self.blanks > 0 {
+                    self.width = self.block_width(lines, idx);
+                }
+                let mut text = key_text(key);
+                let pad = self.width.saturating_sub(text.chars().count());
+                text.extend(::std::iter::repeat_n(' ', pad));
+                text.push_str(self.eq());
+                let column = self.indent_width(level) + text.chars().count();
+                text.push_str(&self.value(item, level, column));
+                let comment = item.trivia().comment.trim_end();
+                if !comment.is_empty() {
+                    text.push(' ');
+                    text.push_str(comment);
+                }
+                self.write(level, &text);
+                self.at_start = false;
+            }
+            Line::Header {
+                level,
+                key,
+                aot,
+                comment,
+            } => {
+                if !prev_comment {
+                    self.gap();
+                }
+                let (open, close) = if aot { ("[[", "]]") } else { ("[", "]") };
+                let mut text = format!("{}{}{}", open, key_text(key), close);
+                if !comment.is_empty() {
+                    text.push(' ');
+                    text.push_str(comment);
+                }
+                self.write(level, &text);
+                self.at_start = true;
+            }
+        }
+    }
+
+    /// Sets the blank lines before a table header.
+    fn gap(&mut self) {
+        self.blanks = self.options.blank_lines_between_tables;
+    }
+
+    /// Writes the line `text` at the indentation level `level`, after the
+    /// blank lines due before it.
+    fn write(&mut self, level: usize, text: &str) {
+        if !self.out.is_empty() {
+            for _ in 0..self.blanks {
+                self.out.push_str(::NL);
+            }
+        }
+        self.blanks = 0;
+        for _ in 0..level {
+            self.out.push_str(&self.options.indent_string);
+        }
+        self.out.push_str(text);
+        self.out.push_str(::NL);
+    }
+
+    /// Returns the width of the widest key of the block of key/values that
+    /// starts at `idx`, or 0 if the entries are not aligned.
+    fn block_width(&self, lines: &[Line], idx: usize) -> usize {
+        if !self.options.align_entries {
+            return 0;
+        }
+        lines[idx..]
+            .iter()
+            .map_while(|line| match *line {
+                Line::Entry { key, .. } => Some(key_text(key).chars().count()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn eq(&self) -> &'static str {
+        if self.options.space_around_eq {
+            " = "
+        } else {
+            "="
+        }
+    }
+
+    fn indent_width(&self, level: usize) -> usize {
+        level * self.options.indent_string.chars().count()
+    }
+
+    /// Returns the text of the value `item`, on a line at the indentation
+    /// level `level`, starting at the column `column`.
+    fn value(&self, item: &Item, level: usize, column: usize) -> String {
+        match *item {
+            Item::Array { ref val, .. } => self.array(val, level, column),
+            Item::InlineTable { ref val, .. } => {
+                let entries: Vec<_> = val.body
+                    .iter()
+                    .filter_map(|(key, item)| key.as_ref().map(|key| (key, item)))
+                    .map(|(key, item)| {
+                        let text = format!("{}{}", key_text(key), self.eq());
+                        let value = self.value(item, level, column + text.len());
+                        text + &value
+                    })
+                    .collect();
+                if entries.is_empty() {
+                    "{}".to_string()
+                } else if self.options.inline_table_spacing {
+                    format!("{{ {} }}", entries.join(", "))
+                } else {
+                    format!("{{{}}}", entries.join(", "))
+                }
+            }
+            _ => item.as_string(),
+        }
+    }
+
+    /// Returns the text of an array whose items are `items`, on one line if
+    /// it fits and holds no comments, or else one element per line.
+    fn array(&self, items: &[Item], level: usize, column: usize) -> String {
+        // The elements, each with the comment at the end of its line, and
+        // the comment lines between them.
+        let mut elems: Vec<(String, &str)> = Vec::new();
+        let mut comments: Vec<(usize, &str)> = Vec::new();
+        let mut same_line = false;
+        let inner = self.indent_width(level + 1);
+        for item in items {
+            match *item {
+                Item::WS(ws) => same_line &= !ws.contains('\n'),
+                Item::Comment(ref trivia) => {
+                    let comment = trivia.comment.trim_end();
+                    match elems.last_mut() {
+                        Some(elem) if same_line && elem.1.is_empty() => elem.1 = comment,
+                        _ if !comment.is_empty() => comments.push((elems.len(), comment)),
+                        _ => {}
+                    }
+                    same_line = false;
+                }
+                ref item => {
+                    elems.push((self.value(item, level + 1, inner), ""));
+                    same_line = true;
+                }
+            }
+        }
+        if elems.is_empty() && comments.is_empty() {
+            return "[]".to_string();
+        }
+
+        let one_line = comments.is_empty() &&
+            elems.iter().all(|e| e.1.is_empty() && !e.0.contains('\n'));
+        if one_line {
+            let values: Vec<_> = elems.iter().map(|e| e.0.as_str()).collect();
+            let text = if self.options.array_spacing {
+                format!("[ {} ]", values.join(", "))
+            } else {
+                format!("[{}]", values.join(", "))
+            };
+            if column + text.chars().count() <= self.options.column_width {
+                return text;
+            }
+        }
+
+        let indent = |level| self.options.indent_string.repeat(level);
+        let mut text = "[".to_string();
+        let mut comments = comments.into_iter().peekable();
+        let count = elems.len();
+        for (idx, (value, comment)) in elems.into_iter().enumerate() {
+            while let Some((_, line)) = comments.next_if(|c| c.0 == idx) {
+                text.push_str(&format!("{}{}{}", ::NL, indent(level + 1), line));
+            }
+            text.push_str(&format!("{}{}{}", ::NL, indent(level + 1), value));
+            if idx + 1 < count || self.options.trailing_comma {
+                text.push(',');
+            }
+            if !comment.is_empty() {
+                text.push(' ');
+                text.push_str(comment);
+            }
+        }
+        for (_, line) in comments {
+            text.push_str(&format!("{}{}{}", ::NL, indent(level + 1), line));
+        }
+        text.push_str(&format!("{}{}]", ::NL, indent(level)));
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+    use super::*;
+
+    fn check(src: &str, options: &FormatOptions, expected: &str) {
+        let doc = Parser::new(src).parse().unwrap();
+        let out = format(&doc, options);
+        assert_eq!(out, expected.replace('\n', ::NL));
+        // The output parses, and is laid out already.
+        let again = Parser::new(&out).parse().unwrap();
+        assert_eq!(format(&again, options), out);
+    }
+
+    #[test]
+    fn layout() {
+        let src = "\n\n# Title\ntitle   =  \"x\"\n\n\n\nnum=1 # one\n#  Owner\n\n\
+                   [ owner ]   # o\n\n\
+                   name = 'a'\npoint = {x = 1, y = 2}\n[[bin]]\nname = 'b'\n\n\n[[bin]]\n\
+                   # second\nname = 'c'\n";
+        let expected = "# Title\ntitle = \"x\"\n\nnum = 1 # one\n#  Owner\n\n[owner] # o\n\
+                        name = 'a'\npoint = {x = 1, y = 2}\n\n[[bin]]\nname = 'b'\n\n\
+                        [[bin]]\n# second\nname = 'c'\n";
+        check(src, &FormatOptions::default(), expected);
+
+        let options = FormatOptions {
+            space_around_eq: false,
+            inline_table_spacing: true,
+            blank_lines_between_tables: 0,
+            ..FormatOptions::default()
+        };
+        let expected = "# Title\ntitle=\"x\"\n\nnum=1 # one\n#  Owner\n[owner] # o\n\
+                        name='a'\npoint={ x=1, y=2 }\n[[bin]]\nname='b'\n\
+                        [[bin]]\n# second\nname='c'\n";
+        check(src, &options, expected);
+    }
+
+    #[test]
+    fn indent_and_align() {
+        let src = "[a]\nx = 1\nlong_name = 2\n\nz = 3\n# About b\n[a.b]\ny = 'v' # c\n\
+                   [a.b.c]\nw = true\n";
+        let options = FormatOptions {
+            indent_tables: true,
+            align_entries: true,
+            ..FormatOptions::default()
+        };
+        let expected = "[a]\nx         = 1\nlong_name = 2\n\nz = 3\n\n  # About b\n  [a.b]\n  \
+                        y = 'v' # c\n\n    [a.b.c]\n    w = true\n";
+        check(src, &options, expected);
+    }
+
+    #[test]
+    fn arrays() {
+        let src = "a = [ 1,2,\n3 ]\nb = [\n  1, # one\n  # two\n  2\n]\nc = [[1, 2], [3]]\n\
+                   d = [\"aaaaaaaaaaaaaaaa\", \"bbbbbbbbbbbbbbbb\", \"cccccccccccccccc\"]\n\
+                   e = []\n";
+        let options = FormatOptions {
+            column_width: 40,
+            ..FormatOptions::default()
+        };
+        let expected = "a = [1, 2, 3]\nb = [\n  1, # one\n  # two\n  2,\n]\nc = [[1, 2], [3]]\n\
+                        d = [\n  \"aaaaaaaaaaaaaaaa\",\n  \"bbbbbbbbbbbbbbbb\",\n  \
+                        \"cccccccccccccccc\",\n]\ne = []\n";
+        check(src, &options, expected);
+
+        let options = FormatOptions {
+            array_spacing: true,
+            trailing_comma: false,
+            ..FormatOptions::default()
+        };
+        let expected = "a = [ 1, 2, 3 ]\nb = [\n  1, # one\n  # two\n  2\n]\n\
+                        c = [ [ 1, 2 ], [ 3 ] ]\n\
+                        d = [ \"aaaaaaaaaaaaaaaa\", \"bbbbbbbbbbbbbbbb\", \
+                        \"cccccccccccccccc\" ]\ne = []\n";
+        check(src, &options, expected);
+    }
+
+    /// Applies `edits`, which are in order, to `src`.
+    fn apply(src: &str, edits: &[TextEdit]) -> String {
+        let mut out = src.to_string();
+        for edit in edits.iter().rev() {
+            out.replace_range(edit.range.clone(), &edit.new_text);
+        }
+        out
+    }
+
+    #[test]
+    fn range() {
+        let src = "a=1\nb =  [1,2]\n\n\n[t]\nc='x'   # c\nd = 2\n[u]\ne=3";
+        let options = FormatOptions::default();
+        let formatted = format(&Parser::new(src).parse().unwrap(), &options);
+
+        // The whole text gives the same as `format`.
+        let edits = format_range(src, 0..src.len(), &options).unwrap();
+        assert_eq!(apply(src, &edits), formatted);
+
+        // Only the lines of `[t]` and `c`, with the blank line above.
+        let start = src.find("\n\n").unwrap() + 1;
+        let end = src.find("# c").unwrap();
+        let edits = format_range(src, start..end, &options).unwrap();
+        let expected = "a=1\nb =  [1,2]\n\n[t]\nc = 'x' # c\nd = 2\n[u]\ne=3";
+        assert_eq!(apply(src, &edits), expected.replace('\n', ::NL));
+        assert_eq!(edits.len(), 2);
+        assert_eq!(
+            edits[1],
+            TextEdit {
+                range: 22..28,
+                new_text: " = 'x'".to_string(),
+            }
+        );
+
+        // A position stands for its line.
+        let pos = src.find("[u]").unwrap() + 1;
+        let edits = format_range(src, pos..pos, &options).unwrap();
+        let expected = "a=1\nb =  [1,2]\n\n\n[t]\nc='x'   # c\nd = 2\n\n[u]\ne=3";
+        assert_eq!(apply(src, &edits), expected.replace('\n', ::NL));
+        let edits = format_range(src, src.len()..src.len(), &options).unwrap();
+        assert_eq!(apply(src, &edits), src.replace("e=3", "e = 3\n"));
+    }
+}
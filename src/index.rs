@@ -30,7 +30,7 @@ impl<'a> Index<&'static str> for Container<'a> {
         let k = Key {
             t: KeyType::Bare,
             sep: "",
-            key: name,
+            key: name.into(),
         };
         let idx = self.map.get(&k).expect("Invalid key");
         &self.body[*idx].1
@@ -42,7 +42,7 @@ impl<'a> IndexMut<&'static str> for Container<'a> {
         let k = Key {
             t: KeyType::Bare,
             sep: "",
-            key: name,
+            key: name.into(),
         };
         let idx = self.map.get(&k).expect("Invalid key");
         &mut self.body[*idx].1
@@ -83,7 +83,7 @@ impl<'a> Index<&'static str> for Item<'a> {
         let k = Key {
             t: KeyType::Bare,
             sep: "",
-            key: name,
+            key: name.into(),
         };
 
         use self::Item::*;
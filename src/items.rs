@@ -1,7 +1,10 @@
 //! Items are the basic elements of a `TOMLDocument`.
 
+use std::borrow::Cow;
+
 use chrono::{DateTime as ChronoDateTime, FixedOffset};
 use container::Container;
+use tomlchar::TOMLChar;
 
 /// Type of TOML string.
 ///
@@ -113,6 +116,11 @@ pub enum KeyType {
 }
 
 /// A key value.
+///
+/// `key` holds the source text of the key, without the quotes of a quoted
+/// key. A bare `Key` may hold a dotted key, such as the name of a table
+/// header, whose segments are given by `segments`. Two keys are equal when
+/// their segments decode to the same names, whatever their quoting.
 #[derive(Clone)]
 pub struct Key<'a> {
     /// The type of the key
@@ -120,16 +128,84 @@ pub struct Key<'a> {
     /// The key separator
     pub sep: &'a str,
     /// The actual key value
-    pub key: &'a str,
+    ///
+    /// It borrows the source text, or owns a name given through the API, as
+    /// by `rename_key`. It used to be a `&'a str`: code that builds a `Key`
+    /// with a string slice writes `key: "x".into()`, or calls `Key::new`,
+    /// and code that reads it calls `as_str`.
+    pub key: Cow<'a, str>,
+}
+
+/// A segment of a dotted key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeySegment<'k> {
+    /// The type of the segment.
+    pub t: KeyType,
+    /// The source text of the segment, without quotes.
+    pub raw: &'k str,
+    /// The name the segment stands for, with escapes decoded.
+    pub value: Cow<'k, str>,
+    /// Whitespace before the segment.
+    pub before: &'k str,
+    /// Whitespace after the segment.
+    pub after: &'k str,
 }
 
 impl<'a> Key<'a> {
     /// Creates a new bare key with a standard separator
-    pub fn new(k: &'a str) -> Key<'a> {
+    pub fn new<K: Into<Cow<'a, str>>>(k: K) -> Key<'a> {
         Key {
             t: KeyType::Bare,
             sep: " = ",
-            key: k,
+            key: k.into(),
+        }
+    }
+
+    /// Creates a key for the name `name` with a standard separator, quoted
+    /// if the name cannot be written as a bare key.
+    pub fn for_name<K: Into<Cow<'a, str>>>(name: K) -> Key<'a> {
+        let name = name.into();
+        let control = name.chars().any(|c| c.is_control() && c != '\t');
+        let (t, key) = if !name.is_empty() && name.chars().all(|c| c.is_bare_key_char()) {
+            (KeyType::Bare, name)
+        } else if !control && !name.contains('\\') && !name.contains('"') {
+            (KeyType::Basic, name)
+        } else if !control && !name.contains('\'') {
+            (KeyType::Literal, name)
+        } else {
+            let mut escaped = String::with_capacity(name.len());
+            for c in name.chars() {
+                match c {
+                    '\\' | '"' => {
+                        escaped.push('\\');
+                        escaped.push(c);
+                    }
+                    c if c.is_control() => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+                    c => escaped.push(c),
+                }
+            }
+            (KeyType::Basic, escaped.into())
+        };
+        Key { t, sep: " = ", key }
+    }
+
+    /// Creates a dotted key from the names of its segments, each quoted if
+    /// it cannot be written bare.
+    pub fn dotted(names: &[&str]) -> Key<'a> {
+        let segments: Vec<_> = names.iter().map(|n| Key::for_name(*n).as_string()).collect();
+        Key::new(segments.join("."))
+    }
+
+    /// Returns the text of the key, as held by `key`.
+    pub fn as_str(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the segments of the key. A quoted key has a single segment.
+    pub fn segments(&self) -> KeySegments<'_> {
+        KeySegments {
+            t: self.t.clone(),
+            rest: Some(&self.key),
         }
     }
 
@@ -143,17 +219,144 @@ impl<'a> Key<'a> {
     }
 }
 
+/// An iterator over the segments of a `Key`.
+#[derive(Debug, Clone)]
+pub struct KeySegments<'k> {
+    t: KeyType,
+    rest: Option<&'k str>,
+}
+
+impl<'k> Iterator for KeySegments<'k> {
+    type Item = KeySegment<'k>;
+
+    fn next(&mut self) -> Option<KeySegment<'k>> {
+        let text = self.rest.take()?;
+        if self.t != KeyType::Bare {
+            // A quoted key is a single segment, stored without its quotes.
+            return Some(segment(self.t.clone(), text, "", ""));
+        }
+
+        let start = text.len() - text.trim_start().len();
+        let (t, raw_start, raw_end, end) = match text[start..].chars().next() {
+            Some(q @ '"') | Some(q @ '\'') => {
+                let mut escaped = false;
+                let close = text[start + 1..]
+                    .char_indices()
+                    .find(|&(_, c)| {
+                        let found = c == q && !escaped;
+                        escaped = q == '"' && c == '\\' && !escaped;
+                        found
+                    })
+                    .map_or(text.len(), |(i, _)| start + 1 + i);
+                let t = if q == '"' { KeyType::Basic } else { KeyType::Literal };
+                (t, start + 1, close, (close + 1).min(text.len()))
+            }
+            _ => {
+                let dot = text[start..].find('.').map_or(text.len(), |i| start + i);
+                let end = start + text[start..dot].trim_end().len();
+                (KeyType::Bare, start, end, end)
+            }
+        };
+        let after = end + (text[end..].len() - text[end..].trim_start().len());
+        if text[after..].starts_with('.') {
+            self.rest = Some(&text[after + 1..]);
+        }
+        Some(segment(t, &text[raw_start..raw_end], &text[..start], &text[end..after]))
+    }
+}
+
+/// Returns a segment of type `t` with its decoded value.
+fn segment<'k>(t: KeyType, raw: &'k str, before: &'k str, after: &'k str) -> KeySegment<'k> {
+    let value = match t {
+        KeyType::Basic if raw.contains('\\') => Cow::Owned(unescape(raw)),
+        _ => Cow::Borrowed(raw),
+    };
+    KeySegment {
+        t,
+        raw,
+        value,
+        before,
+        after,
+    }
+}
+
+/// Returns the value of a string of type `t` whose text between the quotes
+/// is `raw`.
+pub(crate) fn decode_string<'a>(t: &StringType, raw: &'a str) -> Cow<'a, str> {
+    let raw = match *t {
+        StringType::MLB | StringType::MLL => raw
+            .strip_prefix("\r\n")
+            .or_else(|| raw.strip_prefix('\n'))
+            .unwrap_or(raw),
+        _ => raw,
+    };
+    match *t {
+        StringType::SLB | StringType::MLB if raw.contains('\\') => Cow::Owned(unescape(raw)),
+        _ => Cow::Borrowed(raw),
+    }
+}
+
+/// Decodes the escape sequences of a basic string. A backslash at the end
+/// of a line drops the whitespace that follows it, newlines included.
+fn unescape(raw: &str) -> String {
+    let mut s = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            s.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('b') => s.push('\u{8}'),
+            Some('t') => s.push('\t'),
+            Some('n') => s.push('\n'),
+            Some('f') => s.push('\u{c}'),
+            Some('r') => s.push('\r'),
+            Some(c @ 'u') | Some(c @ 'U') => {
+                let len = if c == 'u' { 4 } else { 8 };
+                let hex: String = chars.by_ref().take(len).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(::std::char::from_u32) {
+                    Some(c) => s.push(c),
+                    None => {
+                        s.push('\\');
+                        s.push(c);
+                        s.push_str(&hex);
+                    }
+                }
+            }
+            Some(c) if c.is_whitespace() => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            Some(c) => s.push(c),
+            None => s.push('\\'),
+        }
+    }
+    s
+}
+
 impl<'a> Eq for Key<'a> {}
 
 impl<'a> PartialEq for Key<'a> {
     fn eq(&self, other: &Key) -> bool {
-        self.key == other.key
+        let mut segments = self.segments();
+        let mut others = other.segments();
+        loop {
+            match (segments.next(), others.next()) {
+                (Some(a), Some(b)) if a.value == b.value => {}
+                (None, None) => return true,
+                _ => return false,
+            }
+        }
     }
 }
 
 impl<'a> ::std::hash::Hash for Key<'a> {
     fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
-        self.key.hash(state);
+        for segment in self.segments() {
+            segment.value.hash(state);
+        }
     }
 }
 
@@ -284,7 +487,7 @@ impl<'a> Item<'a> {
                     })
                     .collect::<HashSet<_>>()
                     .len();
-                t == 1
+                t <= 1
 
             }
             _ => unreachable!(),
@@ -408,4 +611,37 @@ mod tests {
         key.t = KeyType::Literal;
         assert_eq!(key.delimiter(), "'");
     }
+
+    #[test]
+    fn Key_segments() {
+        let key = Key::new("a . \"b.c\".'d'");
+        let segments: Vec<_> = key.segments().collect();
+        assert_eq!(segments.len(), 3);
+        assert_eq!((segments[0].raw, segments[0].after), ("a", " "));
+        assert_eq!((segments[1].t.clone(), segments[1].raw), (KeyType::Basic, "b.c"));
+        assert_eq!(segments[1].before, " ");
+        assert_eq!((segments[2].t.clone(), segments[2].raw), (KeyType::Literal, "d"));
+        assert_eq!(key, Key::dotted(&["a", "b.c", "d"]));
+        assert_ne!(key, Key::new("a.b.c.d"));
+    }
+
+    #[test]
+    fn Key_eq() {
+        let basic = Key {
+            t: KeyType::Basic,
+            sep: "",
+            key: "caf\\u00E9 \\\"x\\\"".into(),
+        };
+        assert_eq!(basic.as_str(), "caf\\u00E9 \\\"x\\\"");
+        assert_eq!(basic.segments().next().unwrap().value, "caf\u{e9} \"x\"");
+        assert_eq!(basic, Key::for_name("caf\u{e9} \"x\""));
+        assert_eq!(Key::new("a"), Key::for_name("a"));
+        assert_ne!(Key::new("a.b"), Key::for_name("a.b"));
+        assert_eq!(Key::for_name("a b").t, KeyType::Basic);
+        assert_eq!(Key::for_name("a\"b").as_string(), "'a\"b'");
+
+        let mut map = ::std::collections::HashMap::new();
+        map.insert(basic, 1);
+        assert_eq!(map.get(&Key::for_name("caf\u{e9} \"x\"")), Some(&1));
+    }
 }
@@ -0,0 +1,772 @@
+//! Conversion between documents and JSON.
+//!
+//! This module is available with the `json` feature. `to_json` writes the
+//! values of a document as JSON, with the tables regrouped by segment as in
+//! `de`, and the comments and formatting left out. JSON has no datetimes,
+//! nor NaN and infinite floats: `JsonOptions` selects what they become.
+//!
+//! `from_json` builds a new document from JSON text, laid out like the ones
+//! written by `ser`: objects of a few short values become inline tables,
+//! the other objects `[tables]`, and arrays of them `[[aots]]`. TOML has
+//! no null, so the `null` entries of objects are left out.
+//!
+//! `to_tagged_json` and `from_tagged_json` do the same with the tagged JSON
+//! of the [toml-test](https://github.com/BurntSushi/toml-test) suite, which
+//! the `toml-test-decoder` and `toml-test-encoder` examples use.
+//!
+//! ```
+//! use Molten::json::{from_json, to_json, JsonOptions};
+//! use Molten::parser::Parser;
+//!
+//! let doc = Parser::new("[server]\nhost = 'x' # main\nport = 80\n").parse().unwrap();
+//! let json = to_json(&doc, JsonOptions::default()).unwrap();
+//! assert_eq!(json, r#"{"server":{"host":"x","port":80}}"#);
+//!
+//! let mut buf = String::new();
+//! let doc = from_json(r#"{"server": {"ports": [80, 443]}}"#, &mut buf, JsonOptions::default())
+//!     .unwrap();
+//! assert_eq!(doc.as_string(), "server = {ports = [80, 443]}\n");
+//! ```
+
+use std::str::FromStr;
+
+use chrono::DateTime as ChronoDateTime;
+
+use items::*;
+use errors::*;
+use tree::{entries, item_node, Node};
+use parser::{line_col, Parser};
+use ser::{write_table, Value};
+use tomldoc::TOMLDocument;
+
+/// How `to_json` writes datetimes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DateTimes {
+    /// As a string holding the datetime as written in the document.
+    #[default]
+    Raw,
+    /// As a string holding the datetime in RFC 3339 form, with its offset
+    /// written out, e.g. `1979-05-27T07:32:00+00:00`.
+    Rfc3339,
+    /// As the number of seconds since the Unix epoch.
+    Timestamp,
+}
+
+/// How `to_json` writes NaN and infinite floats.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialFloats {
+    /// As the strings `"nan"`, `"inf"` and `"-inf"`.
+    #[default]
+    String,
+    /// As `null`.
+    Null,
+    /// As an error.
+    Error,
+}
+
+/// Options for the conversion to and from JSON.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// How datetimes are written.
+    pub datetimes: DateTimes,
+    /// How NaN and infinite floats are written.
+    pub special_floats: SpecialFloats,
+    /// Write each entry on a line of its own, indented by two spaces per
+    /// level.
+    pub pretty: bool,
+    /// Read the strings that hold RFC 3339 datetimes as datetimes, in
+    /// `from_json`.
+    pub detect_datetimes: bool,
+}
+
+/// Returns the JSON text of the values of `doc`.
+pub fn to_json(doc: &TOMLDocument, options: JsonOptions) -> Result<String> {
+    let mut writer = Writer::new(options, false);
+    writer.node(&Node::Table(entries(doc, 0, None), None), "", 0)?;
+    Ok(writer.out)
+}
+
+/// Returns the JSON text of the value of `item`.
+pub fn item_to_json(item: &Item, options: JsonOptions) -> Result<String> {
+    let mut writer = Writer::new(options, false);
+    writer.node(&item_node(item), "", 0)?;
+    Ok(writer.out)
+}
+
+/// Returns the values of `doc` in the tagged JSON of the toml-test suite,
+/// where each scalar is an object holding its type and its text, e.g.
+/// `{"type": "integer", "value": "80"}`.
+pub fn to_tagged_json(doc: &TOMLDocument) -> Result<String> {
+    let mut writer = Writer::new(JsonOptions::default(), true);
+    writer.node(&Node::Table(entries(doc, 0, None), None), "", 0)?;
+    Ok(writer.out)
+}
+
+/// Builds a document from the JSON text of an object. The text of the
+/// document is kept in `buf`, from which its items borrow.
+pub fn from_json<'a>(
+    src: &str,
+    buf: &'a mut String,
+    options: JsonOptions,
+) -> Result<TOMLDocument<'a>> {
+    let value = to_value(read(src)?, "", options)?;
+    build(value, buf)
+}
+
+/// Builds a document from the tagged JSON of the toml-test suite, as
+/// written by `to_tagged_json`.
+pub fn from_tagged_json<'a>(src: &str, buf: &'a mut String) -> Result<TOMLDocument<'a>> {
+    let value = from_tagged(read(src)?, "")?;
+    build(Some(value), buf)
+}
+
+/// Reads the JSON text `src`, which holds a single value.
+fn read(src: &str) -> Result<Json> {
+    let mut reader = Reader { src, pos: 0 };
+    let json = reader.value()?;
+    reader.ws();
+    if reader.pos < src.len() {
+        return Err(reader.error("expected the end of the input"));
+    }
+    Ok(json)
+}
+
+/// Writes the table `value` into `buf`, and parses it as a document.
+fn build(value: Option<Value>, buf: &mut String) -> Result<TOMLDocument<'_>> {
+    let entries = match value {
+        Some(Value::Table { entries, .. }) => entries,
+        _ => {
+            let msg = "only objects can be read as a document";
+            bail!(ErrorKind::Json(String::new(), msg.into()))
+        }
+    };
+
+    buf.clear();
+    write_table(buf, "", &entries, false);
+    Parser::new(buf).parse()
+}
+
+/// Returns `path` followed by the table key `name`, or by an index if
+/// `name` is one in brackets.
+fn child(path: &str, name: &str) -> String {
+    if name.starts_with('[') {
+        return format!("{}{}", path, name);
+    }
+    let name = Key::for_name(name).as_string();
+    if path.is_empty() {
+        name
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+/// Returns the array of `values`, found at `path`, if they all have the same
+/// type.
+fn array(values: Vec<Value>, path: &str) -> Result<Value> {
+    if let Some(first) = values.first() {
+        let kind = ::std::mem::discriminant(first);
+        if values.iter().any(|v| ::std::mem::discriminant(v) != kind) {
+            let msg = "arrays of mixed types have no TOML equivalent".to_string();
+            bail!(ErrorKind::Json(path.to_string(), msg))
+        }
+    }
+    Ok(Value::Array(values))
+}
+
+/// Writes the nodes of a document as JSON.
+struct Writer {
+    out: String,
+    options: JsonOptions,
+    /// Write scalars in the tagged form of the toml-test suite.
+    tagged: bool,
+}
+
+impl Writer {
+    fn new(options: JsonOptions, tagged: bool) -> Writer {
+        Writer {
+            out: String::new(),
+            options,
+            tagged,
+        }
+    }
+
+    /// Writes `node`, found at `path` and nested `level` deep.
+    fn node(&mut self, node: &Node, path: &str, level: usize) -> Result<()> {
+        match *node {
+            Node::Table(ref entries, _) => {
+                self.out.push('{');
+                for (idx, (name, node)) in entries.iter().enumerate() {
+                    if idx > 0 {
+                        self.out.push(',');
+                    }
+                    self.newline(level + 1);
+                    self.out.push_str(&string(name));
+                    self.out.push_str(if self.options.pretty { ": " } else { ":" });
+                    self.node(node, &child(path, name), level + 1)?;
+                }
+                if !entries.is_empty() {
+                    self.newline(level);
+                }
+                self.out.push('}');
+            }
+            Node::Array(ref items, _) => {
+                self.out.push('[');
+                for (idx, node) in items.iter().enumerate() {
+                    if idx > 0 {
+                        self.out.push(',');
+                    }
+                    self.newline(level + 1);
+                    self.node(node, &child(path, &format!("[{}]", idx)), level + 1)?;
+                }
+                if !items.is_empty() {
+                    self.newline(level);
+                }
+                self.out.push(']');
+            }
+            Node::Value(item, _) => {
+                let text = self.value(item, path)?;
+                self.out.push_str(&text);
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts a new line indented for `level`, in pretty mode.
+    fn newline(&mut self, level: usize) {
+        if self.options.pretty {
+            self.out.push('\n');
+            for _ in 0..level {
+                self.out.push_str("  ");
+            }
+        }
+    }
+
+    /// Returns the JSON text of a scalar value.
+    fn value(&self, item: &Item, path: &str) -> Result<String> {
+        if self.tagged {
+            return tagged(item);
+        }
+        Ok(match *item {
+            Item::Integer { val, .. } => val.to_string(),
+            Item::Float { val, .. } if val.is_finite() => format!("{:?}", val),
+            Item::Float { val, raw, .. } => match self.options.special_floats {
+                SpecialFloats::String if val.is_nan() => string("nan"),
+                SpecialFloats::String if val > 0.0 => string("inf"),
+                SpecialFloats::String => string("-inf"),
+                SpecialFloats::Null => "null".to_string(),
+                SpecialFloats::Error => bail!(ErrorKind::Json(
+                    path.to_string(),
+                    format!("`{}` has no JSON equivalent", raw)
+                )),
+            },
+            Item::Bool { val, .. } => val.to_string(),
+            Item::Str { .. } => string(&item.as_str()?),
+            Item::DateTime { val, raw, .. } => match self.options.datetimes {
+                DateTimes::Raw => string(raw),
+                DateTimes::Rfc3339 => string(&val.to_rfc3339()),
+                DateTimes::Timestamp => val.timestamp().to_string(),
+            },
+            _ => "null".to_string(),
+        })
+    }
+}
+
+/// Returns the tagged JSON of a scalar value.
+fn tagged(item: &Item) -> Result<String> {
+    let (kind, text) = match *item {
+        Item::Integer { val, .. } => ("integer", val.to_string()),
+        Item::Float { val, .. } if val.is_nan() => ("float", "nan".to_string()),
+        Item::Float { val, .. } if val.is_infinite() => {
+            ("float", if val > 0.0 { "inf" } else { "-inf" }.to_string())
+        }
+        Item::Float { val, .. } => ("float", format!("{:?}", val)),
+        Item::Bool { val, .. } => ("bool", val.to_string()),
+        Item::Str { .. } => ("string", item.as_str()?.into_owned()),
+        Item::DateTime { raw, .. } => ("datetime", raw.to_string()),
+        _ => return Ok("null".to_string()),
+    };
+    Ok(format!("{{\"type\":{},\"value\":{}}}", string(kind), string(&text)))
+}
+
+/// Returns `s` as a JSON string.
+fn string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A value read from JSON text.
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    /// A number, as written.
+    Number(String),
+    Str(String),
+    Array(Vec<Json>),
+    /// An object, whose entries are in the order of their first occurrence.
+    Object(Vec<(String, Json)>),
+}
+
+/// Returns the value of `json`, found at `path`, or `None` for `null`.
+fn to_value(json: Json, path: &str, options: JsonOptions) -> Result<Option<Value>> {
+    Ok(Some(match json {
+        Json::Null => return Ok(None),
+        Json::Bool(b) => Value::Bool(b),
+        Json::Number(ref n) if !n.contains(['.', 'e', 'E']) && i64::from_str(n).is_ok() => {
+            Value::Integer(i64::from_str(n)?)
+        }
+        Json::Number(n) => match f64::from_str(&n)? {
+            f if f.is_finite() => Value::Float(f),
+            _ => {
+                let msg = format!("number `{}` is out of range", n);
+                bail!(ErrorKind::Json(path.to_string(), msg))
+            }
+        },
+        // The parser reads a datetime up to the first space.
+        Json::Str(s) if options.detect_datetimes && !s.contains(' ') &&
+            ChronoDateTime::parse_from_rfc3339(&s).is_ok() =>
+        {
+            Value::DateTime(s)
+        }
+        Json::Str(s) => Value::Str(s),
+        Json::Array(items) => {
+            let mut values = Vec::with_capacity(items.len());
+            for (idx, item) in items.into_iter().enumerate() {
+                let path = child(path, &format!("[{}]", idx));
+                match to_value(item, &path, options)? {
+                    Some(value) => values.push(value),
+                    None => bail!(ErrorKind::Json(path, "null has no TOML equivalent".into())),
+                }
+            }
+            array(values, path)?
+        }
+        Json::Object(entries) => {
+            let mut values = Vec::with_capacity(entries.len());
+            for (name, json) in entries {
+                if let Some(value) = to_value(json, &child(path, &name), options)? {
+                    values.push((name, value));
+                }
+            }
+            Value::map(values)
+        }
+    }))
+}
+
+/// Returns the value of the tagged JSON `json`, found at `path`.
+fn from_tagged(json: Json, path: &str) -> Result<Value> {
+    let (kind, text) = match json {
+        Json::Object(ref entries) if entries.len() == 2 => {
+            match (&entries[0], &entries[1]) {
+                ((t, Json::Str(kind)), (v, Json::Str(text))) if t == "type" && v == "value" => {
+                    (kind.clone(), text.clone())
+                }
+                ((v, Json::Str(text)), (t, Json::Str(kind))) if t == "type" && v == "value" => {
+                    (kind.clone(), text.clone())
+                }
+                _ => return from_tagged_table(json, path),
+            }
+        }
+        Json::Object(_) => return from_tagged_table(json, path),
+        Json::Array(items) => {
+            let mut values = Vec::with_capacity(items.len());
+            for (idx, item) in items.into_iter().enumerate() {
+                values.push(from_tagged(item, &child(path, &format!("[{}]", idx)))?);
+            }
+            return array(values, path);
+        }
+        _ => bail!(ErrorKind::Json(path.to_string(), "expected a tagged value".into())),
+    };
+
+    let value = match kind.as_str() {
+        "string" => Some(Value::Str(text.clone())),
+        "integer" => i64::from_str(&text).ok().map(Value::Integer),
+        "float" => match text.trim_start_matches(['+', '-']) {
+            "nan" => Some(Value::Float(f64::NAN)),
+            "inf" if text.starts_with('-') => Some(Value::Float(f64::NEG_INFINITY)),
+            "inf" => Some(Value::Float(f64::INFINITY)),
+            _ => f64::from_str(&text).ok().map(Value::Float),
+        },
+        "bool" => bool::from_str(&text).ok().map(Value::Bool),
+        "datetime" => ChronoDateTime::parse_from_rfc3339(&text)
+            .ok()
+            .map(|_| Value::DateTime(text.clone())),
+        _ => bail!(ErrorKind::Json(path.to_string(), format!("unsupported type `{}`", kind))),
+    };
+    match value {
+        Some(value) => Ok(value),
+        None => bail!(ErrorKind::Json(
+            path.to_string(),
+            format!("invalid {} `{}`", kind, text)
+        )),
+    }
+}
+
+/// Returns the table of the tagged JSON object `json`, found at `path`.
+fn from_tagged_table(json: Json, path: &str) -> Result<Value> {
+    let mut values = Vec::new();
+    if let Json::Object(entries) = json {
+        for (name, json) in entries {
+            let value = from_tagged(json, &child(path, &name))?;
+            values.push((name, value));
+        }
+    }
+    Ok(Value::map(values))
+}
+
+/// Reads JSON text.
+struct Reader<'s> {
+    src: &'s str,
+    pos: usize,
+}
+
+impl<'s> Reader<'s> {
+    /// Returns an error for the current position.
+    fn error(&self, msg: &str) -> Error {
+        let (line, col) = line_col(self.src, self.pos);
+        let msg = format!("{} at line {} column {}", msg, line, col);
+        ErrorKind::Json(String::new(), msg).into()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.as_bytes().get(self.pos).cloned()
+    }
+
+    /// Skips whitespace.
+    fn ws(&mut self) {
+        while matches!(self.peek(), Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips whitespace and the character `c`.
+    fn expect(&mut self, c: u8) -> Result<()> {
+        self.ws();
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected `{}`", c as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Reads a value, after any whitespace.
+    fn value(&mut self) -> Result<Json> {
+        self.ws();
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Json::Str),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn literal(&mut self, text: &str, json: Json) -> Result<Json> {
+        if !self.src[self.pos..].starts_with(text) {
+            return Err(self.error("expected a value"));
+        }
+        self.pos += text.len();
+        Ok(json)
+    }
+
+    fn number(&mut self) -> Result<Json> {
+        let start = self.pos;
+        while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E') |
+            Some(b'0'..=b'9') = self.peek()
+        {
+            self.pos += 1;
+        }
+        let text = &self.src[start..self.pos];
+        let digits = text.trim_start_matches('-');
+        let leading_zero = digits.len() > 1 && digits.starts_with('0') &&
+            !digits[1..].starts_with(['.', 'e', 'E']);
+        if f64::from_str(text).is_err() || leading_zero || !digits.starts_with(char::is_numeric) {
+            self.pos = start;
+            return Err(self.error("invalid number"));
+        }
+        Ok(Json::Number(text.to_string()))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect(b'"')?;
+        let mut s = String::new();
+        loop {
+            let c = match self.src[self.pos..].chars().next() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string")),
+            };
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                '\\' => {
+                    self.pos += 1;
+                    s.push(self.escape()?);
+                }
+                c if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string"));
+                }
+                c => {
+                    self.pos += c.len_utf8();
+                    s.push(c);
+                }
+            }
+        }
+    }
+
+    /// Reads an escape sequence, after the backslash.
+    fn escape(&mut self) -> Result<char> {
+        let c = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.pos += 1;
+                let mut code = self.hex()?;
+                if (0xD800..0xDC00).contains(&code) && self.src[self.pos..].starts_with("\\u") {
+                    // A surrogate pair.
+                    self.pos += 2;
+                    let low = self.hex()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("invalid unicode escape"));
+                    }
+                    code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                }
+                return match ::std::char::from_u32(code) {
+                    Some(c) => Ok(c),
+                    None => Err(self.error("invalid unicode escape")),
+                };
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+        self.pos += 1;
+        Ok(c)
+    }
+
+    /// Reads four hexadecimal digits.
+    fn hex(&mut self) -> Result<u32> {
+        let digits = self.src.get(self.pos..self.pos + 4).unwrap_or("");
+        match u32::from_str_radix(digits, 16) {
+            Ok(code) if digits.len() == 4 && !digits.starts_with('+') => {
+                self.pos += 4;
+                Ok(code)
+            }
+            _ => Err(self.error("invalid unicode escape")),
+        }
+    }
+
+    fn array(&mut self) -> Result<Json> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.ws();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json> {
+        self.expect(b'{')?;
+        let mut entries: Vec<(String, Json)> = Vec::new();
+        self.ws();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            self.ws();
+            let name = self.string()?;
+            self.expect(b':')?;
+            let value = self.value()?;
+            // The last of duplicate keys wins.
+            match entries.iter().position(|e| e.0 == name) {
+                Some(pos) => entries[pos].1 = value,
+                None => entries.push((name, value)),
+            }
+            self.ws();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(entries));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+    use super::*;
+
+    const SRC: &str = "title = \"Tab\\there\" # c\nwhen = 1979-05-27T07:32:00Z\n\
+                       low = -inf\nmax = +nan\n\n[[bin]]\nname = 'a'\n\n\
+                       [[bin]]\nname = 'b'\n\n[deps.serde]\nversion = \"1\"\n";
+
+    #[test]
+    fn export() {
+        let doc = Parser::new(SRC).parse().unwrap();
+        let json = to_json(&doc, JsonOptions::default()).unwrap();
+        assert_eq!(
+            json,
+            "{\"title\":\"Tab\\there\",\"when\":\"1979-05-27T07:32:00Z\",\"low\":\"-inf\",\
+             \"max\":\"nan\",\"bin\":[{\"name\":\"a\"},{\"name\":\"b\"}],\
+             \"deps\":{\"serde\":{\"version\":\"1\"}}}"
+        );
+
+        let options = JsonOptions {
+            datetimes: DateTimes::Timestamp,
+            special_floats: SpecialFloats::Null,
+            pretty: true,
+            ..JsonOptions::default()
+        };
+        let json = to_json(&doc, options).unwrap();
+        assert!(json.starts_with("{\n  \"title\": \"Tab\\there\",\n  \"when\": 296638320,\n"));
+        assert!(json.contains("\"low\": null,\n  \"max\": null,\n  \"bin\": [\n    {\n"));
+        assert!(json.ends_with("\n    }\n  }\n}"));
+
+        let options = JsonOptions {
+            datetimes: DateTimes::Rfc3339,
+            ..JsonOptions::default()
+        };
+        let json = item_to_json(&doc["when"], options).unwrap();
+        assert_eq!(json, "\"1979-05-27T07:32:00+00:00\"");
+
+        let options = JsonOptions {
+            special_floats: SpecialFloats::Error,
+            ..JsonOptions::default()
+        };
+        let err = to_json(&doc, options).unwrap_err();
+        assert_eq!(err.to_string(), "`-inf` has no JSON equivalent at `low`");
+    }
+
+    #[test]
+    fn import() {
+        let src = r#"{
+            "name": "café \"x\"", "n": 3, "f": 1.5e3, "skip": null,
+            "when": "1979-05-27T07:32:00Z",
+            "point": {"x": 1, "y": 2},
+            "bin": [
+                {"name": "a", "tags": [], "path": "src/bin/with/a/rather/long/name.rs"},
+                {"name": "b", "tags": ["x"], "path": "src/bin/b.rs", "test": false}
+            ],
+            "server": {"host": "localhost", "options": {"a": true}}
+        }"#;
+        let mut buf = String::new();
+        let options = JsonOptions {
+            detect_datetimes: true,
+            ..JsonOptions::default()
+        };
+        let doc = from_json(src, &mut buf, options).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "name = 'café \"x\"'\nn = 3\nf = 1500.0\nwhen = 1979-05-27T07:32:00Z\n\
+             point = {x = 1, y = 2}\n\n[[bin]]\nname = \"a\"\ntags = []\n\
+             path = \"src/bin/with/a/rather/long/name.rs\"\n\n\
+             [[bin]]\nname = \"b\"\ntags = [\"x\"]\n\
+             path = \"src/bin/b.rs\"\ntest = false\n\n\
+             [server]\nhost = \"localhost\"\noptions = {a = true}\n"
+        );
+        assert_eq!(
+            to_json(&doc, JsonOptions::default()).unwrap(),
+            "{\"name\":\"café \\\"x\\\"\",\"n\":3,\"f\":1500.0,\
+             \"when\":\"1979-05-27T07:32:00Z\",\"point\":{\"x\":1,\"y\":2},\
+             \"bin\":[{\"name\":\"a\",\"tags\":[],\
+             \"path\":\"src/bin/with/a/rather/long/name.rs\"},\
+             {\"name\":\"b\",\"tags\":[\"x\"],\"path\":\"src/bin/b.rs\",\"test\":false}],\
+             \"server\":{\"host\":\"localhost\",\"options\":{\"a\":true}}}"
+        );
+    }
+
+    #[test]
+    fn import_errors() {
+        let mut buf = String::new();
+        let options = JsonOptions::default();
+        let err = from_json("{\"a\": [1, null]}", &mut buf, options).unwrap_err();
+        assert_eq!(err.to_string(), "null has no TOML equivalent at `a[1]`");
+        let err = from_json("{\"a\":\n 01}", &mut buf, options).unwrap_err();
+        assert_eq!(err.to_string(), "invalid number at line 1 column 1");
+        let err = from_json("[1]", &mut buf, options).unwrap_err();
+        assert_eq!(err.to_string(), "only objects can be read as a document");
+        let err = from_json("{\"a\": 1} x", &mut buf, options).unwrap_err();
+        assert_eq!(err.to_string(), "expected the end of the input at line 0 column 9");
+        let err = from_json("{\"a\": {\"b\": -1e400}}", &mut buf, options).unwrap_err();
+        assert_eq!(err.to_string(), "number `-1e400` is out of range at `a.b`");
+        let err = from_json("{\"a\": \"\\uD800\\u0041\"}", &mut buf, options).unwrap_err();
+        assert_eq!(err.to_string(), "invalid unicode escape at line 0 column 19");
+        let err = from_json("{\"a\": [1, \"x\"]}", &mut buf, options).unwrap_err();
+        assert_eq!(err.to_string(), "arrays of mixed types have no TOML equivalent at `a`");
+        let err = from_json("{\"a\": {\"b\": [{\"x\": 1}, 2]}}", &mut buf, options).unwrap_err();
+        assert_eq!(err.to_string(), "arrays of mixed types have no TOML equivalent at `a.b`");
+        let doc = from_json("{\"a\": [[1], [\"x\"]]}", &mut buf, options).unwrap();
+        assert_eq!(doc.as_string(), "a = [[1], [\"x\"]]\n");
+    }
+
+    #[test]
+    fn tagged() {
+        let src = "a = 1 # c\nb = [1.5, -inf, +inf, +nan]\n\n[t]\ns = \"x\\ty\"\n\
+                   d = 1979-05-27T07:32:00Z\n";
+        let doc = Parser::new(src).parse().unwrap();
+        let json = to_tagged_json(&doc).unwrap();
+        assert_eq!(
+            json,
+            "{\"a\":{\"type\":\"integer\",\"value\":\"1\"},\
+             \"b\":[{\"type\":\"float\",\"value\":\"1.5\"},\
+             {\"type\":\"float\",\"value\":\"-inf\"},\
+             {\"type\":\"float\",\"value\":\"inf\"},\
+             {\"type\":\"float\",\"value\":\"nan\"}],\
+             \"t\":{\"s\":{\"type\":\"string\",\"value\":\"x\\ty\"},\
+             \"d\":{\"type\":\"datetime\",\"value\":\"1979-05-27T07:32:00Z\"}}}"
+        );
+
+        let mut buf = String::new();
+        let doc = from_tagged_json(&json, &mut buf).unwrap();
+        assert_eq!(to_tagged_json(&doc).unwrap(), json);
+
+        let json = "{\"a\": {\"type\": \"float\", \"value\": \"nan\"}, \
+                    \"b\": {\"type\": \"float\", \"value\": \"+inf\"}}";
+        let doc = from_tagged_json(json, &mut buf).unwrap();
+        assert_eq!(doc["a"].as_string(), "+nan");
+        assert_eq!(doc["b"].as_string(), "+inf");
+
+        let json = "{\"a\": {\"value\": \"x\", \"type\": \"integer\"}}";
+        let err = from_tagged_json(json, &mut buf).unwrap_err();
+        assert_eq!(err.to_string(), "invalid integer `x` at `a`");
+    }
+}
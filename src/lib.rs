@@ -15,6 +15,9 @@ and, eventually, adding that functionality to
 ## Non-Goals
 - *Error recovery*: Molten does not try to correct recoverable errors.
 - *Serialization/Deserialization*: See [toml-rs](https://github.com/alexcrichton/toml-rs) for this.
+  The optional `serde` feature can still read typed values out of a parsed document,
+  to avoid parsing it twice, and write them out as a new document or into an existing one.
+  The `json` feature converts documents to and from JSON.
 
 
 # Setup
@@ -55,14 +58,43 @@ extern crate pretty_assertions;
 extern crate chrono;
 #[macro_use]
 extern crate error_chain;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
 
 mod tomlchar;
+mod array;
+mod sort;
+mod compact;
+mod semantic;
+mod tree;
+mod convert;
+mod comments;
 pub mod errors;
 pub mod tomldoc;
 pub mod parser;
 pub mod api;
 pub mod index;
 pub mod items;
+pub mod aot;
+pub mod entry;
+pub mod section;
+pub mod walk;
+pub mod visit;
+pub mod format;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "serde")]
+pub mod update;
+#[cfg(feature = "serde")]
+pub mod spanned;
+#[cfg(feature = "json")]
+pub mod json;
 
 #[doc(inline)]
 pub use tomldoc::TOMLDocument;
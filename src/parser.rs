@@ -14,6 +14,18 @@ use std::str::{FromStr, CharIndices};
 #[allow(non_camel_case_types, dead_code)]
 type isAOT = bool;
 
+/// Converts a byte offset into `src` to a (line, column) pair, as given by
+/// `ErrorKind::ParseError`.
+///
+/// All indexes are 0-based, and the column counts bytes from the start of
+/// the line, whether it ends with `\n` or `\r\n`.
+pub fn line_col(src: &str, offset: usize) -> (usize, usize) {
+    let before = &src.as_bytes()[..offset.min(src.len())];
+    let line = before.iter().filter(|&&b| b == b'\n').count();
+    let start = before.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    (line, offset - start)
+}
+
 /// Parser for TOML documents.
 #[derive(Debug)]
 pub struct Parser<'a> {
@@ -118,14 +130,7 @@ impl<'a> Parser<'a> {
     ///
     /// All indexes are 0-based.
     fn to_linecol(&self, offset: usize) -> (usize, usize) {
-        let mut cur = 0;
-        for (i, line) in self.src.lines().enumerate() {
-            if cur + line.len() + 1 > offset {
-                return (i, offset - cur);
-            }
-            cur += line.len() + 1;
-        }
-        (self.src.lines().count(), 0)
+        line_col(self.src, offset)
     }
 
     /// Create a generic "parse error" at the current position.
@@ -178,12 +183,13 @@ impl<'a> Parser<'a> {
 
         // Switch to parsing tables/arrays of tables until the end of the input.
         while !self.end() {
+            let (_, name) = self.peek_table()?;
             let (k, v) = self.parse_table()?;
             let v = match v {
                 Item::Table { is_aot_elem, .. } if is_aot_elem => {
                     // This is just the first table in an AoT. Parse the rest of the array
                     // along with it.
-                    self.parse_aot(v, k.key)?
+                    self.parse_aot(v, name)?
                 }
                 _ => v,
             };
@@ -350,6 +356,17 @@ impl<'a> Parser<'a> {
                     trivia: trivia,
                 })
             }
+            // Special floats, without a sign: inf and nan
+            'i' | 'n' if ["inf", "nan"].iter().any(|w| self.src[self.idx..].starts_with(w)) => {
+                self.inc_n(3);
+                let raw = self.extract();
+
+                Ok(Item::Float {
+                    val: if raw == "inf" { f64::INFINITY } else { f64::NAN },
+                    trivia: trivia,
+                    raw,
+                })
+            }
             // Array
             '[' => {
                 let mut elems: Vec<Item> = Vec::new();
@@ -399,9 +416,15 @@ impl<'a> Parser<'a> {
                 self.inc();
 
                 while self.current != '}' {
+                    let mut comma = false;
                     while self.current.is_ws() || self.current == ',' {
+                        comma |= self.current == ',';
                         self.inc();
                     }
+                    // Spaces may end the table, but a trailing comma may not.
+                    if self.current == '}' && !comma {
+                        break;
+                    }
                     let (key, val) = self.parse_key_value(false)?;
                     let _ = elems.append(key, val)?;
                 }
@@ -496,7 +519,6 @@ impl<'a> Parser<'a> {
 
     /// Parses a string element
     fn parse_string(&mut self, delim: char) -> Result<Item<'a>> {
-        // TODO: Handle escaping.
         let mut multiline = false;
         let mut str_type = if delim == '\'' {
             StringType::SLL
@@ -528,7 +550,11 @@ impl<'a> Parser<'a> {
         self.mark();
 
         'outer: loop {
-            if self.current == delim {
+            if self.current == '\\' && delim == '"' {
+                // Skip the escaped character, which may be a quote.
+                self.inc() || return Err(self.error(ErrorKind::UnexpectedEof));
+                self.inc() || return Err(self.error(ErrorKind::UnexpectedEof));
+            } else if self.current == delim {
                 let val = self.extract_exact();
                 if multiline {
                     for _ in 0..3 {
@@ -581,7 +607,7 @@ impl<'a> Parser<'a> {
         Key {
             t: key_type,
             sep: "",
-            key: key,
+            key: key.into(),
         }
     }
 
@@ -594,7 +620,7 @@ impl<'a> Parser<'a> {
         Key {
             t: KeyType::Bare,
             sep: "",
-            key: key,
+            key: key.into(),
         }
     }
 
@@ -656,7 +682,7 @@ impl<'a> Parser<'a> {
         let key = Key {
             t: KeyType::Bare,
             sep: "",
-            key: name.clone(),
+            key: name.into(),
         };
         self.inc(); // Skip closing bracket.
         if is_aot {
@@ -681,6 +707,14 @@ impl<'a> Parser<'a> {
 
                     if Parser::is_child(name, name_next) {
                         let (key_next, table_next) = self.parse_table()?;
+                        let table_next = match table_next {
+                            // A child AoT that ends the input only has its
+                            // first table parsed.
+                            Item::Table { is_aot_elem, .. } if is_aot_elem => {
+                                self.parse_aot(table_next, name_next)?
+                            }
+                            _ => table_next,
+                        };
                         values.append(key_next, table_next)?;
                     } else {
                         let table = Item::Table {
@@ -760,6 +794,16 @@ mod tests {
         });
     }
 
+    #[test]
+    fn special_floats() {
+        let doc = Parser::new("a = inf\nb = [nan, -inf]\nc = {x = +nan}\n").parse().unwrap();
+        assert!(matches!(doc["a"], Item::Float { val, .. } if val == f64::INFINITY));
+        assert!(matches!(doc["b"][0], Item::Float { val, .. } if val.is_nan()));
+        assert!(matches!(doc["b"][1], Item::Float { val, .. } if val == f64::NEG_INFINITY));
+        assert!(matches!(doc["c"]["x"], Item::Float { val, .. } if val.is_nan()));
+        assert!(Parser::new("a = infinity\n").parse().is_err());
+    }
+
     #[test]
     fn issue41() {
         let text = ::std::str::from_utf8(b"\'\'fb\'\xee\x9d\xbd").unwrap();
@@ -771,4 +815,22 @@ mod tests {
         let text = ::std::str::from_utf8(b"\'\nv\'f%\nb").unwrap();
         let _ = Parser::new(text).parse();
     }
+
+    #[test]
+    fn escaped_quotes() {
+        let doc = Parser::new("a = \"x \\\" y\"\nb = 1\n").parse().unwrap();
+        assert_eq!(doc["a"].as_str().unwrap(), "x \" y");
+        assert_eq!(doc["b"].as_string(), "1");
+    }
+
+    #[test]
+    fn inline_table_spacing() {
+        let doc = Parser::new("a = { x = 1, y = 2 }\nb = {}\nc = { }\n").parse().unwrap();
+        assert_eq!(doc["a"]["y"].as_string(), "2");
+        assert!(doc["b"].is_inline_table());
+        assert!(doc["c"].is_inline_table());
+        assert!(Parser::new("a = {x = 1,}\n").parse().is_err());
+        assert!(Parser::new("a = { x = 1, }\n").parse().is_err());
+        assert!(Parser::new("a = {,}\n").parse().is_err());
+    }
 }
@@ -0,0 +1,385 @@
+//! Moving whole table sections around a `Container`.
+//!
+//! A section is a table or an AoT together with the comment lines right
+//! above its header; its body includes the child tables the parser nested
+//! under it. The blank lines that separate two sections are stored at the
+//! end of the body of the first one. When sections are moved, those gaps
+//! stay in place, so that the spacing between headers and at the end of the
+//! container doesn't change.
+
+use std::mem;
+
+use items::*;
+use errors::*;
+use container::{is_table_like, Container};
+
+/// A table section taken out of a container.
+#[derive(Debug, Clone)]
+pub struct Section<'a> {
+    pub(crate) key: Key<'a>,
+    pub(crate) item: Item<'a>,
+    pub(crate) comments: Vec<Item<'a>>,
+    pub(crate) gap: Vec<&'a str>,
+}
+
+impl<'a> Section<'a> {
+    /// Returns the key of the section.
+    pub fn key(&self) -> &Key<'a> {
+        &self.key
+    }
+
+    /// Returns the table or AoT of the section.
+    pub fn item(&self) -> &Item<'a> {
+        &self.item
+    }
+
+    /// Returns the mutable table or AoT of the section.
+    pub fn item_mut(&mut self) -> &mut Item<'a> {
+        &mut self.item
+    }
+}
+
+impl<'a> Container<'a> {
+    /// Removes the table or AoT `key` from the container, along with its
+    /// header comments, and returns it.
+    pub fn take_section(&mut self, key: &Key<'a>) -> Result<Section<'a>> {
+        self.section_index(key)?;
+        let (mut sections, mut gaps) = self.detach();
+        let idx = sections.iter().rposition(|s| s.key == *key).unwrap();
+        let mut section = sections.remove(idx);
+        if sections.is_empty() {
+            // What ends the container replaces the blank lines that set the
+            // section apart from the values.
+            let mut blank = Vec::new();
+            while let Some(&(None, Item::WS(s))) = self.body.last() {
+                blank.insert(0, s);
+                self.body.pop();
+            }
+            let end = gaps.pop().unwrap();
+            self.body.extend(end.into_iter().map(|s| (None, Item::WS(s))));
+            self.rebuild_map();
+            section.gap = blank;
+            return Ok(section);
+        }
+        // The last section keeps what ends the container.
+        let gap = if idx + 1 == gaps.len() {
+            idx - 1
+        } else {
+            idx
+        };
+        section.gap = gaps.remove(gap);
+        self.attach(sections, gaps);
+        Ok(section)
+    }
+
+    /// Inserts `section` after the last table of the container.
+    pub fn insert_section(&mut self, section: Section<'a>) -> Result<()> {
+        let idx = self.body.iter().filter(|e| is_table_like(&e.1)).count();
+        self.insert_section_at(idx, section)
+    }
+
+    /// Inserts `section` before the table or AoT `before`.
+    pub fn insert_section_before(&mut self, before: &Key<'a>, section: Section<'a>) -> Result<()> {
+        let idx = self.section_index(before)?;
+        self.insert_section_at(idx, section)
+    }
+
+    /// Inserts `section` after the table or AoT `after`.
+    pub fn insert_section_after(&mut self, after: &Key<'a>, section: Section<'a>) -> Result<()> {
+        let idx = self.section_index(after)?;
+        self.insert_section_at(idx + 1, section)
+    }
+
+    /// Moves the table or AoT `key` before the table or AoT `before`.
+    pub fn move_before(&mut self, key: &Key<'a>, before: &Key<'a>) -> Result<()> {
+        self.check_move(key, before)?;
+        let section = self.take_section(key)?;
+        self.insert_section_before(before, section)
+    }
+
+    /// Moves the table or AoT `key` after the table or AoT `after`.
+    pub fn move_after(&mut self, key: &Key<'a>, after: &Key<'a>) -> Result<()> {
+        self.check_move(key, after)?;
+        let section = self.take_section(key)?;
+        self.insert_section_after(after, section)
+    }
+
+    /// Checks that `key` can be moved next to `other`.
+    fn check_move(&self, key: &Key<'a>, other: &Key<'a>) -> Result<()> {
+        self.section_index(key)?;
+        self.section_index(other)?;
+        if key == other {
+            bail!(ErrorKind::InvalidPosition);
+        }
+        Ok(())
+    }
+
+    /// Returns the index of `key` among the tables and AoTs of the container.
+    pub(crate) fn section_index(&self, key: &Key<'a>) -> Result<usize> {
+        let pos = match self.map.get(key) {
+            Some(&pos) => pos,
+            None => bail!(ErrorKind::NonExistentKey(key.as_string())),
+        };
+        if !is_table_like(&self.body[pos].1) {
+            bail!(ErrorKind::APIWrongItem);
+        }
+        Ok(self.body[..pos].iter().filter(|e| is_table_like(&e.1)).count())
+    }
+
+    /// Inserts `section` so that it becomes the section at index `idx`.
+    fn insert_section_at(&mut self, idx: usize, section: Section<'a>) -> Result<()> {
+        if self.map.contains_key(&section.key) {
+            bail!(ErrorKind::DuplicateKey(section.key.key.to_string()));
+        }
+        let (mut sections, mut gaps) = self.detach();
+        let len = sections.len();
+        if len == 0 {
+            // The first table takes over the whitespace that ends the
+            // container, and is set apart from the values by a blank line.
+            let mut end = Vec::new();
+            while let Some(&(None, Item::WS(s))) = self.body.last() {
+                end.insert(0, s);
+                self.body.pop();
+            }
+            if !self.body.is_empty() {
+                end_line(self);
+                self.body.push((None, Item::WS(::NL)));
+            }
+            gaps.push(end);
+        } else {
+            // Use the spacing found between the existing sections if any.
+            let sep = if len >= 2 {
+                gaps[len - 2].clone()
+            } else {
+                section.gap.clone()
+            };
+            if idx == len {
+                gaps.insert(len - 1, sep);
+            } else {
+                gaps.insert(idx, sep);
+            }
+        }
+        sections.insert(idx, section);
+        self.attach(sections, gaps);
+        Ok(())
+    }
+
+    /// Takes the sections out of the body, along with the whitespace that
+    /// follows each of them.
+    pub(crate) fn detach(&mut self) -> (Vec<Section<'a>>, Vec<Vec<&'a str>>) {
+        let first = match self.body.iter().position(|e| is_table_like(&e.1)) {
+            Some(pos) => self.leading_start(pos),
+            None => return (Vec::new(), Vec::new()),
+        };
+
+        let mut sections: Vec<Section<'a>> = Vec::new();
+        let mut comments = Vec::new();
+        for (key, item) in self.body.drain(first..) {
+            let key = match key {
+                Some(key) if is_table_like(&item) => {
+                    sections.push(Section {
+                        key,
+                        item,
+                        comments: mem::take(&mut comments),
+                        gap: Vec::new(),
+                    });
+                    continue;
+                }
+                key => key,
+            };
+            // Anything between two sections is rendered as part of the
+            // first one, so that's where it goes.
+            match sections.last_mut().and_then(|s| tail_mut(&mut s.item)) {
+                Some(tail) => {
+                    tail.body.push((key, item));
+                    tail.rebuild_map();
+                }
+                None => comments.push(item),
+            }
+        }
+
+        let count = sections.len();
+        let mut gaps = Vec::with_capacity(count);
+        for idx in 0..count {
+            let mut lead = Vec::new();
+            let mut gap = Vec::new();
+            if let Some(tail) = tail_mut(&mut sections[idx].item) {
+                // The comments at the very end lead up to the next header.
+                if idx + 1 < count {
+                    while let Some(&(None, Item::Comment(_))) = tail.body.last() {
+                        lead.insert(0, tail.body.pop().unwrap().1);
+                    }
+                }
+                while let Some(&(None, Item::WS(s))) = tail.body.last() {
+                    gap.insert(0, s);
+                    tail.body.pop();
+                }
+            }
+            gaps.push(gap);
+            if !lead.is_empty() {
+                let next = &mut sections[idx + 1].comments;
+                lead.append(next);
+                *next = lead;
+            }
+        }
+        (sections, gaps)
+    }
+
+    /// Puts `sections` back at the end of the body, each one followed by the
+    /// whitespace at the same index in `gaps`.
+    pub(crate) fn attach(&mut self, sections: Vec<Section<'a>>, gaps: Vec<Vec<&'a str>>) {
+        for (section, gap) in sections.into_iter().zip(gaps) {
+            let Section {
+                key,
+                mut item,
+                comments,
+                ..
+            } = section;
+            let prev = match self.body.last_mut() {
+                Some(&mut (_, ref mut prev)) if is_table_like(prev) => Some(prev),
+                _ => None,
+            };
+            match prev {
+                Some(prev) => {
+                    end_line_item(prev);
+                    if let Some(tail) = tail_mut(prev) {
+                        tail.body.extend(comments.into_iter().map(|c| (None, c)));
+                    }
+                }
+                None => {
+                    end_line(self);
+                    self.body.extend(comments.into_iter().map(|c| (None, c)));
+                }
+            }
+            if !gap.is_empty() {
+                end_line_item(&mut item);
+            }
+            if let Some(tail) = tail_mut(&mut item) {
+                tail.body.extend(gap.into_iter().map(|s| (None, Item::WS(s))));
+            }
+            self.body.push((Some(key), item));
+        }
+        self.rebuild_map();
+    }
+}
+
+/// Returns the container at the very end of the text of a table or AoT.
+fn tail_mut<'b, 'a>(item: &'b mut Item<'a>) -> Option<&'b mut Container<'a>> {
+    match *item {
+        Item::Table { ref mut val, .. } => Some(::aot::tail_mut(val)),
+        Item::AoT(ref mut tables) => tables.last_mut().and_then(tail_mut),
+        _ => None,
+    }
+}
+
+/// Makes sure that the text of a container ends with a newline.
+fn end_line(c: &mut Container) {
+    match c.body.last_mut() {
+        Some(&mut (_, Item::WS(_))) | None => {}
+        Some(&mut (_, Item::Comment(ref mut trivia))) => end_trail(trivia),
+        Some(&mut (_, ref mut item)) if is_table_like(item) => end_line_item(item),
+        Some(&mut (_, ref mut item)) => end_trail(item.trivia_mut()),
+    }
+}
+
+/// Makes sure that the text of a table or AoT ends with a newline.
+fn end_line_item(item: &mut Item) {
+    match *item {
+        Item::Table {
+            ref mut val,
+            ref mut trivia,
+            ..
+        } => if val.body.is_empty() {
+            end_trail(trivia);
+        } else {
+            end_line(val);
+        },
+        Item::AoT(ref mut tables) => if let Some(table) = tables.last_mut() {
+            end_line_item(table);
+        },
+        _ => {}
+    }
+}
+
+/// Ends `trivia` with a newline if it doesn't have one.
+fn end_trail(trivia: &mut Trivia) {
+    if !trivia.trail.contains('\n') {
+        trivia.trail = ::NL;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+    use items::*;
+
+    #[test]
+    fn move_before() {
+        let src = "[package]\nname = \"x\"\n\n[features]\ndefault = []\n\n\
+                   # Deps\n[dependencies]\nserde = \"1\"\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.move_before(&Key::new("dependencies"), &Key::new("features"))
+            .unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "[package]\nname = \"x\"\n\n# Deps\n[dependencies]\nserde = \"1\"\n\n\
+             [features]\ndefault = []\n"
+        );
+        Parser::new(&doc.as_string()).parse().unwrap();
+    }
+
+    #[test]
+    fn move_after() {
+        let src = "title = \"t\"\n\n[a]\nx = 1\n\n[a.b]\ny = 2\n\n[c]\nz = 3";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.move_after(&Key::new("a"), &Key::new("c")).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            format!(
+                "title = \"t\"\n\n[c]\nz = 3{}\n[a]\nx = 1\n\n[a.b]\ny = 2\n",
+                ::NL
+            )
+        );
+        assert_eq!(doc["a"]["a.b"]["y"].as_string(), "2");
+        assert!(doc.move_after(&Key::new("a"), &Key::new("a")).is_err());
+        assert!(doc.move_after(&Key::new("title"), &Key::new("a")).is_err());
+    }
+
+    #[test]
+    fn pull_out() {
+        let src = "[a]\nx = 1\n\n[a.b]\ny = 2\n\n[a.c]\nz = 3\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let section = doc["a"]
+            .as_table_mut()
+            .unwrap()
+            .take_section(&Key::new("a.b"))
+            .unwrap();
+        assert_eq!(section.key().key, "a.b");
+        assert_eq!(doc.as_string(), "[a]\nx = 1\n\n[a.c]\nz = 3\n");
+        doc.insert_section(section).unwrap();
+        assert_eq!(doc.as_string(), "[a]\nx = 1\n\n[a.c]\nz = 3\n\n[a.b]\ny = 2\n");
+        let section = doc.take_section(&Key::new("a.b")).unwrap();
+        assert!(doc.insert_section_before(&Key::new("x"), section).is_err());
+    }
+
+    #[test]
+    fn aot() {
+        let src = "[[bin]]\nname = \"a\"\n\n[[bin]]\nname = \"b\"\n\n[lib]\npath = \"l\"\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.move_before(&Key::new("lib"), &Key::new("bin")).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "[lib]\npath = \"l\"\n\n[[bin]]\nname = \"a\"\n\n[[bin]]\nname = \"b\"\n"
+        );
+    }
+
+    #[test]
+    fn only_section() {
+        let src = "a = 1\n\n[t]\nx = 1\n\n\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let section = doc.take_section(&Key::new("t")).unwrap();
+        assert_eq!(doc.as_string(), "a = 1\n\n\n");
+        doc.insert_section(section).unwrap();
+        assert_eq!(doc.as_string(), src);
+    }
+}
@@ -0,0 +1,160 @@
+//! Comparison of the values of items and containers.
+//!
+//! The derived `PartialEq` of `Item` and `Container` compares the text of
+//! their values and their trivia: `1_000` differs from `1000`, and a comment
+//! makes two documents differ. `semantic_eq` only compares the values they
+//! decode to:
+//!
+//! - integers, floats, booleans and strings by value, whatever their
+//!   notation and quoting;
+//! - datetimes by the instant and the offset they stand for;
+//! - tables by the values of their keys, in any order, whether they are
+//!   written as `[tables]`, under dotted headers or inline, and arrays of
+//!   tables alike with arrays of inline tables;
+//! - arrays by the values of their elements, in order.
+//!
+//! Floats are equal when `==` says so, so `0.0` equals `-0.0`, and any NaN
+//! equals any other NaN. Whitespace, comments and `Item::None` hold no value:
+//! they equal each other, and no value.
+
+use items::*;
+use container::Container;
+use tree::{entries, header_depth, Node};
+
+impl<'a> Item<'a> {
+    /// Returns `true` if the item holds the same value as `other`, as
+    /// described in the `semantic` module.
+    ///
+    /// ```
+    /// use Molten::parser::Parser;
+    ///
+    /// let a = Parser::new("n = 1_000\n[t]\nx = 'a' # c\n").parse().unwrap();
+    /// let b = Parser::new("n = 1000\nt = {x = \"a\"}\n").parse().unwrap();
+    /// assert!(a["n"].semantic_eq(&b["n"]));
+    /// assert!(a["t"].semantic_eq(&b["t"]));
+    /// assert!(a.semantic_eq(&b));
+    /// ```
+    pub fn semantic_eq(&self, other: &Item) -> bool {
+        if !self.is_value() || !other.is_value() {
+            return self.is_value() == other.is_value();
+        }
+        // The tables under a `[table]` are named from the document root:
+        // they are compared by their names past the prefix they share.
+        let depth = match (header_depth(self), header_depth(other)) {
+            (Some(a), Some(b)) => a.min(b),
+            (depth, None) | (None, depth) => depth.unwrap_or(0),
+        };
+        nodes_eq(&Node::new(self, depth, None, None), &Node::new(other, depth, None, None))
+    }
+}
+
+impl<'a> Container<'a> {
+    /// Returns `true` if the container holds the same values as `other`, as
+    /// described in the `semantic` module.
+    ///
+    /// The names of the tables of both containers are taken as written, as
+    /// in a document: the body of a `[table]` is better compared through the
+    /// table item.
+    pub fn semantic_eq(&self, other: &Container) -> bool {
+        let (a, b) = (entries(self, 0, None), entries(other, 0, None));
+        nodes_eq(&Node::Table(a, None), &Node::Table(b, None))
+    }
+}
+
+/// Returns `true` if the nodes hold the same values.
+fn nodes_eq(a: &Node, b: &Node) -> bool {
+    match (a, b) {
+        (Node::Value(a, _), Node::Value(b, _)) => values_eq(a, b),
+        (Node::Table(a, _), Node::Table(b, _)) => {
+            a.len() == b.len() &&
+                a.iter().all(|(name, a)| {
+                    b.iter().find(|e| e.0 == *name).is_some_and(|(_, b)| nodes_eq(a, b))
+                })
+        }
+        (Node::Array(a, _), Node::Array(b, _)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| nodes_eq(a, b))
+        }
+        _ => false,
+    }
+}
+
+/// Returns `true` if the scalars hold the same value.
+fn values_eq(a: &Item, b: &Item) -> bool {
+    match (a, b) {
+        (Item::Integer { val: a, .. }, Item::Integer { val: b, .. }) => a == b,
+        (Item::Float { val: a, .. }, Item::Float { val: b, .. }) => {
+            a == b || (a.is_nan() && b.is_nan())
+        }
+        (Item::Bool { val: a, .. }, Item::Bool { val: b, .. }) => a == b,
+        (Item::DateTime { val: a, .. }, Item::DateTime { val: b, .. }) => {
+            a == b && a.offset() == b.offset()
+        }
+        (Item::Str { .. }, Item::Str { .. }) => match (a.as_str(), b.as_str()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+
+    #[test]
+    fn values() {
+        let a = Parser::new("a = 1_000\nb = 1e3\nc = \"x\\ty\"\nd = 1979-05-27T07:32:00Z\n\
+                             e = [1, 2] # c\nf = -0.0\ng = +nan\nh = true\n")
+            .parse()
+            .unwrap();
+        let b = Parser::new("h = true\ng = +nan\nf = 0.0\ne = [ 1,\n  2 ]\n\
+                             d = 1979-05-27T07:32:00+00:00\nc = 'x\ty'\nb = 1000.0\na = 1000\n")
+            .parse()
+            .unwrap();
+        assert_ne!(a, b);
+        assert!(a.semantic_eq(&b));
+        assert!(a["g"].semantic_eq(&b["g"]));
+
+        let c = Parser::new("a = 1\nb = 1e3\nc = \"x\\ty\"\nd = 1979-05-27T00:32:00-07:00\n")
+            .parse()
+            .unwrap();
+        assert!(!a.semantic_eq(&c));
+        assert!(!a["a"].semantic_eq(&c["a"]));
+        assert!(!a["a"].semantic_eq(&a["b"]));
+        // The same instant, at another offset.
+        assert!(!a["d"].semantic_eq(&c["d"]));
+        assert!(!a["e"].semantic_eq(&b["f"]));
+    }
+
+    #[test]
+    fn tables() {
+        let a = Parser::new("[a]\nx = 1\n[a.b]\ny = 2\n\n[[t]]\nz = 1\n[t.u]\nw = 2\n")
+            .parse()
+            .unwrap();
+        let b = Parser::new("t = [{z = 1, u = {w = 2}}]\n# A\n[a]\nb = {y = 2}\nx = 1\n")
+            .parse()
+            .unwrap();
+        let c = Parser::new("a = {x = 1, b = {y = 3}}\n[[t]]\nz = 1\n[t.u]\nw = 2\n")
+            .parse()
+            .unwrap();
+        assert!(a.semantic_eq(&b));
+        assert!(a["a"].semantic_eq(&b["a"]));
+        assert!(a["t"].semantic_eq(&b["t"]));
+        assert!(a["t"].semantic_eq(&c["t"]));
+        assert!(!a.semantic_eq(&c));
+        assert!(!a["a"].semantic_eq(&c["a"]));
+
+        // A `[a.b]` before `[a]` is only merged with it in the document.
+        let g = Parser::new("[a.b]\ny = 2\n[a]\nx = 1\n[[t]]\nz = 1\n[t.u]\nw = 2\n")
+            .parse()
+            .unwrap();
+        assert!(a.semantic_eq(&g));
+
+        let d = Parser::new("[x]\n[x.a.b]\ny = 2\n").parse().unwrap();
+        let e = Parser::new("[x]\n[x.a]\n[x.a.b]\ny = 2\n").parse().unwrap();
+        let f = Parser::new("[x]\n[x.c.b]\ny = 2\n").parse().unwrap();
+        assert!(d["x"].semantic_eq(&e["x"]));
+        assert!(d.semantic_eq(&e));
+        assert!(!d.semantic_eq(&f));
+    }
+}
@@ -0,0 +1,752 @@
+//! Serializing Rust types into a formatted document, with serde.
+//!
+//! This module is available with the `serde` feature. The value is written
+//! out as TOML text with a default layout, which is then parsed, so that the
+//! document is as editable as one read from a file:
+//!
+//! - structs become `[tables]`, and vectors of structs `[[aots]]`;
+//! - maps of short scalar values become inline tables, other maps tables;
+//! - `None` fields are left out, and the key/values of a table come before
+//!   its child tables whatever the order of the fields.
+//!
+//! Unit enum variants are written as strings, and the other variants as a
+//! table with a single key naming the variant.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate Molten;
+//!
+//! #[derive(Serialize)]
+//! struct Package {
+//!     name: &'static str,
+//!     version: &'static str,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Manifest {
+//!     package: Package,
+//! }
+//!
+//! # fn main() {
+//! let manifest = Manifest {
+//!     package: Package { name: "molten", version: "0.1.0" },
+//! };
+//! let mut buf = String::new();
+//! let doc = Molten::ser::to_document(&manifest, &mut buf).unwrap();
+//! assert_eq!(doc["package"]["name"].as_string(), "\"molten\"");
+//! # }
+//! ```
+
+use std::fmt::Display;
+
+use serde::ser::{self, Serialize};
+
+use items::*;
+use errors::*;
+use parser::Parser;
+use tomldoc::TOMLDocument;
+use de::within;
+
+/// The widest inline table a map is written as, brackets included.
+const INLINE_WIDTH: usize = 50;
+
+/// Serializes `value` into the text of a TOML document.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    let entries = to_entries(value)?;
+    let mut out = String::new();
+    write_table(&mut out, "", &entries, false);
+    Ok(out)
+}
+
+/// Serializes `value` into a document, whose text is kept in `buf`.
+///
+/// The items of the document borrow from `buf`, which must outlive any
+/// document they are moved to.
+pub fn to_document<'a, T: Serialize + ?Sized>(
+    value: &T,
+    buf: &'a mut String,
+) -> Result<TOMLDocument<'a>> {
+    *buf = to_string(value)?;
+    let src: &'a str = buf;
+    Parser::new(src).parse()
+}
+
+/// Serializes `value` into the entries of the top-level table.
+pub(crate) fn to_entries<T: Serialize + ?Sized>(value: &T) -> Result<Vec<(String, Value)>> {
+    match value.serialize(ValueSerializer)? {
+        Some(Value::Table { entries, .. }) => Ok(entries),
+        _ => Err(ser::Error::custom("only structs and maps can be written as a document")),
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Error {
+        ErrorKind::Serde(String::new(), msg.to_string()).into()
+    }
+}
+
+/// A serialized value, before it is written out.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    /// A datetime, written as its RFC 3339 text.
+    DateTime(String),
+    Array(Vec<Value>),
+    /// A table, which is written inline if `inline` is set.
+    Table {
+        entries: Vec<(String, Value)>,
+        inline: bool,
+    },
+}
+
+impl Value {
+    /// Returns the table for the entries of a map, which is written inline
+    /// if its values are flat and it is short enough.
+    pub(crate) fn map(entries: Vec<(String, Value)>) -> Value {
+        let mut table = Value::Table {
+            entries,
+            inline: false,
+        };
+        let width = inline(&table).len();
+        if let Value::Table { ref entries, ref mut inline } = table {
+            *inline = entries.iter().all(|e| e.1.is_flat()) && width <= INLINE_WIDTH;
+        }
+        table
+    }
+
+    /// Returns `true` if the value is written as a `[table]`.
+    pub(crate) fn is_section(&self) -> bool {
+        matches!(*self, Value::Table { inline: false, .. })
+    }
+
+    /// Returns `true` if the value is written as an `[[aot]]`.
+    fn is_aot(&self) -> bool {
+        match *self {
+            Value::Array(ref items) => !items.is_empty() && items.iter().all(Value::is_section),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the value is a scalar or an array of scalars.
+    fn is_flat(&self) -> bool {
+        match *self {
+            Value::Array(ref items) => items.iter().all(|v| !v.is_container()),
+            Value::Table { .. } => false,
+            _ => true,
+        }
+    }
+
+    fn is_container(&self) -> bool {
+        matches!(*self, Value::Array(_) | Value::Table { .. })
+    }
+}
+
+/// Writes the key/values of a table, and then its child tables under
+/// headers starting with `path`.
+pub(crate) fn write_table(out: &mut String, path: &str, entries: &[(String, Value)], aot: bool) {
+    if !path.is_empty() {
+        if !out.is_empty() {
+            out.push_str(::NL);
+        }
+        let (open, close) = if aot { ("[[", "]]") } else { ("[", "]") };
+        out.push_str(&format!("{}{}{}{}", open, path, close, ::NL));
+    }
+    for (name, value) in entries {
+        if !value.is_section() && !value.is_aot() {
+            let key = Key::for_name(name.as_str()).as_string();
+            out.push_str(&format!("{} = {}{}", key, inline(value), ::NL));
+        }
+    }
+    for (name, value) in entries {
+        let key = Key::for_name(name.as_str()).as_string();
+        let path = if path.is_empty() {
+            key
+        } else {
+            format!("{}.{}", path, key)
+        };
+        match *value {
+            Value::Table { ref entries, .. } if value.is_section() => {
+                write_table(out, &path, entries, false)
+            }
+            Value::Array(ref tables) if value.is_aot() => {
+                for table in tables {
+                    if let Value::Table { ref entries, .. } = *table {
+                        write_table(out, &path, entries, true);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the text of `value` written on a single line.
+fn inline(value: &Value) -> String {
+    match *value {
+        Value::Integer(i) => i.to_string(),
+        // Special floats keep their sign, as the other writers of TOML do.
+        Value::Float(f) if f.is_nan() => "+nan".to_string(),
+        Value::Float(f) if f.is_infinite() => if f > 0.0 { "+inf" } else { "-inf" }.to_string(),
+        Value::Float(f) => format!("{:?}", f),
+        Value::Bool(b) => b.to_string(),
+        Value::Str(ref s) => quote(s),
+        Value::DateTime(ref s) => s.clone(),
+        Value::Array(ref items) => {
+            let items: Vec<_> = items.iter().map(inline).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Table { ref entries, .. } if entries.is_empty() => "{}".to_string(),
+        Value::Table { ref entries, .. } => {
+            let entries: Vec<_> = entries
+                .iter()
+                .map(|(name, value)| {
+                    format!("{} = {}", Key::for_name(name.as_str()).as_string(), inline(value))
+                })
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+/// Returns `s` as a basic string, or as a literal string if that spares
+/// escaping quotes or backslashes.
+fn quote(s: &str) -> String {
+    let control = s.chars().any(|c| c.is_control() && c != '\t');
+    if (s.contains('"') || s.contains('\\')) && !s.contains('\'') && !control {
+        return format!("'{}'", s);
+    }
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Builds a `Value`, or `None` for the values that are left out.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<Value>;
+    type Error = Error;
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeVariant<SerializeArray>;
+    type SerializeMap = SerializeTable;
+    type SerializeStruct = SerializeTable;
+    type SerializeStructVariant = SerializeVariant<SerializeTable>;
+
+    fn serialize_bool(self, v: bool) -> Result<Option<Value>> {
+        Ok(Some(Value::Bool(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<Value>> {
+        Ok(Some(Value::Integer(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<Value>> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<Value>> {
+        if v > i64::MAX as u64 {
+            return Err(ser::Error::custom(format!("{} does not fit in a TOML integer", v)));
+        }
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Option<Value>> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Option<Value>> {
+        Ok(Some(Value::Float(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<Value>> {
+        Ok(Some(Value::Str(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<Value>> {
+        Ok(Some(Value::Str(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Option<Value>> {
+        let bytes = v.iter().map(|&b| Value::Integer(i64::from(b))).collect();
+        Ok(Some(Value::Array(bytes)))
+    }
+
+    fn serialize_none(self) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Option<Value>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<Value>> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Option<Value>> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Option<Value>> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Option<Value>> {
+        let value = value.serialize(self).map_err(|e| within(e, variant))?;
+        Ok(Some(Value::Table {
+            entries: value.into_iter().map(|v| (variant.to_string(), v)).collect(),
+            inline: true,
+        }))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray> {
+        Ok(SerializeArray {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeArray> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeArray>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeTable> {
+        Ok(SerializeTable {
+            entries: Vec::new(),
+            key: None,
+            map: true,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<SerializeTable> {
+        Ok(SerializeTable {
+            entries: Vec::new(),
+            key: None,
+            map: false,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeTable>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_struct(variant, len)?,
+        })
+    }
+}
+
+/// Collects the items of an array.
+struct SerializeArray {
+    items: Vec<Value>,
+}
+
+impl SerializeArray {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let idx = self.items.len();
+        match value.serialize(ValueSerializer) {
+            Ok(Some(value)) => {
+                self.items.push(value);
+                Ok(())
+            }
+            Ok(None) => Err(within(
+                ser::Error::custom("arrays cannot hold missing values"),
+                &format!("[{}]", idx),
+            )),
+            Err(e) => Err(within(e, &format!("[{}]", idx))),
+        }
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        Ok(Some(Value::Array(self.items)))
+    }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        SerializeArray::end(self)
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        SerializeArray::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        SerializeArray::end(self)
+    }
+}
+
+/// Collects the entries of a struct or map.
+struct SerializeTable {
+    entries: Vec<(String, Value)>,
+    /// The key of the map entry whose value comes next.
+    key: Option<String>,
+    /// `true` for a map, which may be written inline.
+    map: bool,
+}
+
+impl SerializeTable {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<()> {
+        let segment = Key::for_name(key.as_str()).as_string();
+        if let Some(value) = value.serialize(ValueSerializer).map_err(|e| within(e, &segment))? {
+            self.entries.push((key, value));
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        if self.map {
+            return Ok(Some(Value::map(self.entries)));
+        }
+        Ok(Some(Value::Table {
+            entries: self.entries,
+            inline: false,
+        }))
+    }
+}
+
+impl ser::SerializeMap for SerializeTable {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = match key.serialize(ValueSerializer)? {
+            Some(Value::Str(s)) => Some(s),
+            _ => return Err(ser::Error::custom("map keys must be strings")),
+        };
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        match self.key.take() {
+            Some(key) => self.insert(key, value),
+            None => Err(ser::Error::custom("map value given before its key")),
+        }
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        SerializeTable::end(self)
+    }
+}
+
+impl ser::SerializeStruct for SerializeTable {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        SerializeTable::end(self)
+    }
+}
+
+/// Wraps the contents of an enum variant in a table named after it.
+struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> SerializeVariant<S> {
+    fn wrap(variant: &'static str, value: Option<Value>) -> Result<Option<Value>> {
+        Ok(Some(Value::Table {
+            entries: value.into_iter().map(|v| (variant.to_string(), v)).collect(),
+            inline: true,
+        }))
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeArray> {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let variant = self.variant;
+        self.inner.push(value).map_err(|e| within(e, variant))
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        let value = self.inner.end()?;
+        Self::wrap(self.variant, value)
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeTable> {
+    type Ok = Option<Value>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let variant = self.variant;
+        self.inner
+            .insert(key.to_string(), value)
+            .map_err(|e| within(e, variant))
+    }
+
+    fn end(self) -> Result<Option<Value>> {
+        let value = self.inner.end()?;
+        Self::wrap(self.variant, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use parser::Parser;
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Source {
+        Registry,
+        Path(String),
+        Git { url: String, rev: Option<String> },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Target {
+        name: String,
+        path: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Package {
+        name: String,
+        version: String,
+        authors: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Manifest {
+        package: Package,
+        bin: Vec<Target>,
+        features: BTreeMap<String, Vec<String>>,
+        env: BTreeMap<String, String>,
+        source: Source,
+        ratio: f64,
+        edition: Option<String>,
+    }
+
+    fn manifest() -> Manifest {
+        let mut features = BTreeMap::new();
+        features.insert("default".to_string(), vec!["std".to_string()]);
+        let mut env = BTreeMap::new();
+        env.insert("PATH".to_string(), "/usr/bin:\"/opt/my bin\"".to_string());
+        env.insert("RUST_LOG".to_string(), "debug".to_string());
+        Manifest {
+            package: Package {
+                name: "molten".to_string(),
+                version: "0.1.0".to_string(),
+                authors: vec!["Leopold".to_string()],
+            },
+            bin: vec![
+                Target {
+                    name: "a".to_string(),
+                    path: "src/a.rs".to_string(),
+                },
+                Target {
+                    name: "b".to_string(),
+                    path: "src/b.rs".to_string(),
+                },
+            ],
+            features,
+            env,
+            source: Source::Git {
+                url: "https://x".to_string(),
+                rev: None,
+            },
+            ratio: 1.0,
+            edition: None,
+        }
+    }
+
+    #[test]
+    fn to_string() {
+        let expected = format!(
+            "features = {{default = [\"std\"]}}{nl}\
+             source = {{Git = {{url = \"https://x\"}}}}{nl}\
+             ratio = 1.0{nl}\
+             {nl}[package]{nl}\
+             name = \"molten\"{nl}\
+             version = \"0.1.0\"{nl}\
+             authors = [\"Leopold\"]{nl}\
+             {nl}[[bin]]{nl}\
+             name = \"a\"{nl}\
+             path = \"src/a.rs\"{nl}\
+             {nl}[[bin]]{nl}\
+             name = \"b\"{nl}\
+             path = \"src/b.rs\"{nl}\
+             {nl}[env]{nl}\
+             PATH = '/usr/bin:\"/opt/my bin\"'{nl}\
+             RUST_LOG = \"debug\"{nl}",
+            nl = ::NL
+        );
+        assert_eq!(super::to_string(&manifest()).unwrap(), expected);
+        assert!(super::to_string(&1).is_err());
+    }
+
+    #[test]
+    fn to_document() {
+        let mut buf = String::new();
+        let doc = super::to_document(&manifest(), &mut buf).unwrap();
+        assert!(doc["package"].is_table());
+        assert!(doc["bin"].is_aot());
+        assert!(doc["features"].is_inline_table());
+        assert_eq!(doc.as_string(), buf);
+
+        let src = "# Hand-written\nname = \"x\"\n";
+        let mut target = Parser::new(src).parse().unwrap();
+        let mut generated = super::to_document(&manifest(), &mut buf).unwrap();
+        let (key, bin) = generated.remove(&Key::new("bin")).unwrap();
+        target.append(key, bin).unwrap();
+        assert!(target.as_string().starts_with("# Hand-written\nname = \"x\"\n"));
+        assert_eq!(target["bin"][1]["name"].as_string(), "\"b\"");
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = super::to_string(&manifest()).unwrap();
+        let manifest: Manifest = ::de::from_str(&text).unwrap();
+        assert_eq!(manifest, self::manifest());
+        let source = Source::Registry;
+        let mut map = BTreeMap::new();
+        map.insert("source", &source);
+        assert_eq!(super::to_string(&map).unwrap(), format!("source = \"Registry\"{}", ::NL));
+    }
+
+    #[test]
+    fn special_floats() {
+        let mut map = BTreeMap::new();
+        map.insert("a", f64::NAN);
+        map.insert("b", f64::INFINITY);
+        map.insert("c", f64::NEG_INFINITY);
+        let mut buf = String::new();
+        let doc = super::to_document(&map, &mut buf).unwrap();
+        assert_eq!(doc["a"].as_string(), "+nan");
+        assert_eq!(doc["b"].as_string(), "+inf");
+        assert_eq!(doc["c"].as_string(), "-inf");
+        let map: BTreeMap<String, f64> = ::de::from_str(&buf).unwrap();
+        assert!(map["a"].is_nan());
+        assert_eq!(map["b"], f64::INFINITY);
+        assert_eq!(map["c"], f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn error_path() {
+        let mut map = BTreeMap::new();
+        map.insert("big", vec![1, u64::MAX]);
+        let err = super::to_string(&map).unwrap_err();
+        assert!(err.to_string().ends_with("at `big[1]`"), "{}", err);
+    }
+}
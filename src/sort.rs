@@ -0,0 +1,146 @@
+//! Sorting of the key/values of a `Container`.
+//!
+//! Each key/value moves together with the comment lines right above it and
+//! its trailing comment, while the whitespace between them stays in place:
+//! the blank lines that split the values into groups keep their positions,
+//! and so does the newline that ends each line.
+
+use std::cmp::Ordering;
+
+use items::*;
+use container::{is_table_like, Container};
+
+/// An entry of a container body.
+type BodyEntry<'a> = (Option<Key<'a>>, Item<'a>);
+
+impl<'a> Container<'a> {
+    /// Sorts the key/values of the container by key.
+    ///
+    /// Keys are compared by the names they stand for, whatever their
+    /// quoting.
+    pub fn sort_keys(&mut self) {
+        self.sort_by(|k1, _, k2, _| compare_keys(k1, k2));
+    }
+
+    /// Sorts the key/values of the container with the comparison function
+    /// `compare`, which is given the key and item of two key/values.
+    ///
+    /// The sort is stable, and tables are left where they are.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&Key<'a>, &Item<'a>, &Key<'a>, &Item<'a>) -> Ordering,
+    {
+        let slots = self.slots();
+        if slots.len() < 2 {
+            return;
+        }
+
+        let trails: Vec<&'a str> = slots
+            .iter()
+            .map(|&(_, pos)| self.body[pos].1.trivia().trail)
+            .collect();
+        let mut body = self.body.drain(..).map(Some).collect::<Vec<_>>();
+        let mut units: Vec<Vec<BodyEntry<'a>>> = slots
+            .iter()
+            .map(|&(start, pos)| {
+                body[start..pos + 1].iter_mut().map(|e| e.take().unwrap()).collect()
+            })
+            .collect();
+        units.sort_by(|a, b| {
+            let (ka, va) = a.last().unwrap();
+            let (kb, vb) = b.last().unwrap();
+            compare(ka.as_ref().unwrap(), va, kb.as_ref().unwrap(), vb)
+        });
+
+        // Put the units back in the slots, each slot keeping its newline.
+        let mut units = units.into_iter().zip(trails);
+        for (idx, entry) in body.into_iter().enumerate() {
+            match entry {
+                Some(entry) => self.body.push(entry),
+                None if slots.iter().any(|&(start, _)| start == idx) => {
+                    let (unit, trail) = units.next().unwrap();
+                    self.body.extend(unit);
+                    self.body.last_mut().unwrap().1.trivia_mut().trail = trail;
+                }
+                None => {}
+            }
+        }
+        self.rebuild_map();
+    }
+
+    /// Returns `true` if the key/values of the container are sorted by key.
+    pub fn is_sorted(&self) -> bool {
+        let keys: Vec<_> = self.slots()
+            .into_iter()
+            .map(|(_, pos)| self.body[pos].0.as_ref().unwrap())
+            .collect();
+        keys.windows(2).all(|w| compare_keys(w[0], w[1]) != Ordering::Greater)
+    }
+
+    /// Returns the range of body positions taken by each key/value, from
+    /// the first comment line above it to the key/value itself.
+    fn slots(&self) -> Vec<(usize, usize)> {
+        self.body
+            .iter()
+            .enumerate()
+            .filter(|&(_, entry)| entry.0.is_some() && !is_table_like(&entry.1))
+            .map(|(pos, _)| (self.leading_start(pos), pos))
+            .collect()
+    }
+}
+
+/// Compares two keys by the names of their segments.
+fn compare_keys(a: &Key, b: &Key) -> Ordering {
+    a.segments().map(|s| s.value).cmp(b.segments().map(|s| s.value))
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+
+    #[test]
+    fn sort_keys() {
+        let src = "[dependencies]\n# JSON\nserde = \"1\" # pinned\nlog = \"0.4\"\n\n\
+                   rand = \"0.5\"\nchrono = \"0.4\"";
+        let mut doc = Parser::new(src).parse().unwrap();
+        {
+            let deps = doc["dependencies"].as_table_mut().unwrap();
+            assert!(!deps.is_sorted());
+            deps.sort_keys();
+            assert!(deps.is_sorted());
+        }
+        assert_eq!(
+            doc.as_string(),
+            "[dependencies]\nchrono = \"0.4\"\nlog = \"0.4\"\n\n\
+             rand = \"0.5\"\n# JSON\nserde = \"1\" # pinned"
+        );
+        assert_eq!(doc["dependencies"]["log"].as_string(), "\"0.4\"");
+        Parser::new(&doc.as_string()).parse().unwrap();
+    }
+
+    #[test]
+    fn sort_by() {
+        let src = "b = 1\na = 22\nc = 333\n\n[t]\nz = 1\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        doc.sort_by(|_, v1, _, v2| v2.as_string().len().cmp(&v1.as_string().len()));
+        assert_eq!(doc.as_string(), "c = 333\na = 22\nb = 1\n\n[t]\nz = 1\n");
+        assert!(!doc.is_sorted());
+    }
+
+    #[test]
+    fn sort_inline_table() {
+        let mut doc = Parser::new("t = {b = 1, a = 2}").parse().unwrap();
+        doc["t"].as_table_mut().unwrap().sort_keys();
+        assert_eq!(doc.as_string(), "t = {a = 2, b = 1}");
+        assert!(doc["t"].as_table().unwrap().is_sorted());
+    }
+
+    #[test]
+    fn sort_quoted_keys() {
+        let mut doc = Parser::new("c = 1\n\"bb\" = 2\na = 3\n'b' = 4\n").parse().unwrap();
+        assert!(!doc.is_sorted());
+        doc.sort_keys();
+        assert_eq!(doc.as_string(), "a = 3\n'b' = 4\n\"bb\" = 2\nc = 1\n");
+        assert!(doc.is_sorted());
+    }
+}
@@ -0,0 +1,192 @@
+//! Values read along with their place in the source text.
+//!
+//! This module is available with the `serde` feature. A `Spanned<T>` field
+//! is read like a `T` by `de::from_str`, and also records the byte range of
+//! the text it was read from: the value itself for a key/value or an array
+//! element, or the header of a table.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate Molten;
+//!
+//! use Molten::spanned::Spanned;
+//!
+//! #[derive(Deserialize)]
+//! struct Server {
+//!     port: Spanned<u16>,
+//! }
+//!
+//! # fn main() {
+//! let src = "# Server\nport = 80\n";
+//! let server: Server = Molten::de::from_str(src).unwrap();
+//! assert_eq!(*server.port.get_ref(), 80);
+//! assert_eq!(&src[server.port.span()], "80");
+//! assert_eq!(server.port.line_col(src), (1, 7));
+//! # }
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use parser::line_col;
+
+/// The name of the struct the deserializer recognizes as a `Spanned`.
+pub(crate) const NAME: &str = "$__Molten_private_Spanned";
+/// The field holding the start of the span.
+pub(crate) const START: &str = "$__Molten_private_start";
+/// The field holding the end of the span.
+pub(crate) const END: &str = "$__Molten_private_end";
+/// The field holding the value.
+pub(crate) const VALUE: &str = "$__Molten_private_value";
+/// The fields of a `Spanned`, in the order they are given.
+pub(crate) const FIELDS: &[&str] = &[START, END, VALUE];
+
+/// A value along with the byte range of the source text it was read from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Spanned<T> {
+    start: usize,
+    end: usize,
+    value: T,
+}
+
+impl<T> Spanned<T> {
+    /// Returns the byte offset of the start of the value.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the byte offset right after the end of the value.
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the byte range of the value in the source text.
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the 0-based line and column at which the value starts in
+    /// `src`, as given by `ErrorKind::ParseError`.
+    pub fn line_col(&self, src: &str) -> (usize, usize) {
+        line_col(src, self.start)
+    }
+
+    /// Returns a reference to the value.
+    pub fn get_ref(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns a mutable reference to the value.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+
+    /// Returns the value, dropping its span.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Spanned<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Spanned<T>, D::Error> {
+        deserializer.deserialize_struct(NAME, FIELDS, SpannedVisitor(PhantomData))
+    }
+}
+
+/// Reads a `Spanned` from the fields given by the deserializer.
+struct SpannedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for SpannedVisitor<T> {
+    type Value = Spanned<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value with its span")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Spanned<T>, A::Error> {
+        expect_key(&mut map, START)?;
+        let start = map.next_value()?;
+        expect_key(&mut map, END)?;
+        let end = map.next_value()?;
+        expect_key(&mut map, VALUE)?;
+        let value = map.next_value()?;
+        Ok(Spanned { start, end, value })
+    }
+}
+
+/// Reads the next key of `map`, which must be `name`.
+fn expect_key<'de, A: MapAccess<'de>>(map: &mut A, name: &'static str) -> Result<(), A::Error> {
+    match map.next_key::<String>()? {
+        Some(ref key) if key == name => Ok(()),
+        _ => Err(de::Error::missing_field(name)),
+    }
+}
+
+impl<T: Serialize> Serialize for Spanned<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.value.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use de::{from_document, from_str};
+    use parser::{line_col, Parser};
+    use super::Spanned;
+
+    #[derive(Debug, Deserialize)]
+    struct Bin {
+        name: Spanned<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        title: Spanned<String>,
+        ports: Spanned<Vec<Spanned<u16>>>,
+        point: BTreeMap<String, Spanned<i64>>,
+        flags: Vec<Spanned<Vec<bool>>>,
+        server: Spanned<BTreeMap<String, String>>,
+        bin: Vec<Spanned<Bin>>,
+        missing: Option<Spanned<bool>>,
+    }
+
+    #[test]
+    fn spans() {
+        let src = "title = \"Molten\"  # name\nports = [ 80, # http\n  443 ]\n\
+                   point = {x = 1, y = -2}\nflags = [[true], [false, true]]\n\n\
+                   [server]\nhost = 'x'\n\n  [[bin]]\nname = \"a\"\n[[bin]]\nname = ''\n";
+        let config: Config = from_str(src).unwrap();
+        let text = |span| &src[span];
+        assert_eq!(text(config.title.span()), "\"Molten\"");
+        assert_eq!(config.title.get_ref(), "Molten");
+        assert_eq!(text(config.ports.span()), "[ 80, # http\n  443 ]");
+        assert_eq!(text(config.ports.get_ref()[1].span()), "443");
+        assert_eq!(config.ports.get_ref()[1].line_col(src), (2, 2));
+        assert_eq!(text(config.point["y"].span()), "-2");
+        assert_eq!(text(config.flags[1].span()), "[false, true]");
+        assert_eq!(text(config.server.span()), "[server]");
+        assert_eq!(text(config.bin[0].span()), "[[bin]]");
+        assert_eq!(config.bin[0].line_col(src), (9, 2));
+        assert_eq!(text(config.bin[1].get_ref().name.span()), "''");
+        assert!(config.missing.is_none());
+
+        let src = "a = 1\r\nb = 2\r\nc = 3\r\n";
+        let point: BTreeMap<String, Spanned<i64>> = from_str(src).unwrap();
+        assert_eq!(point["c"].line_col(src), (2, 4));
+        assert_eq!(line_col(src, src.find('c').unwrap()), (2, 0));
+    }
+
+    #[test]
+    fn no_source() {
+        let doc = Parser::new("name = \"a\"\n").parse().unwrap();
+        let err = from_document::<Bin>(&doc).unwrap_err();
+        assert!(err.to_string().starts_with("the value has no span"));
+    }
+}
@@ -0,0 +1,259 @@
+//! The values of a `Container`, with its tables regrouped by segment.
+//!
+//! A table can be written as a `[table]`, under a dotted header such as
+//! `[a.b]`, or as an inline table: the tree holds the same nodes for all of
+//! them, with the tables of the same name merged. When the source text is
+//! given, each node also records the byte range of its text.
+
+use items::*;
+use container::{is_table_like, Container};
+
+/// The byte range of the text of a node in the source, if it is known.
+pub(crate) type Span = Option<(usize, usize)>;
+
+/// A node of the document tree, with the tables regrouped by segment.
+///
+/// The spans are only read by `de`, with the `serde` feature.
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+pub(crate) enum Node<'b, 'a: 'b> {
+    /// A scalar value.
+    Value(&'b Item<'a>, Span),
+    /// A table or inline table, with its entries by name.
+    Table(Vec<(String, Node<'b, 'a>)>, Span),
+    /// An array or AoT.
+    Array(Vec<Node<'b, 'a>>, Span),
+}
+
+impl<'b, 'a: 'b> Node<'b, 'a> {
+    /// Returns the node of `item`, whose table headers have `depth` segments
+    /// in front of the names of their entries, and whose text is at `span`
+    /// in `src`.
+    pub(crate) fn new(
+        item: &'b Item<'a>,
+        depth: usize,
+        src: Option<&str>,
+        span: Span,
+    ) -> Node<'b, 'a> {
+        match *item {
+            Item::Table { ref val, .. } => Node::Table(entries(val, depth, src), span),
+            Item::InlineTable { ref val, .. } => Node::Table(entries(val, 0, src), span),
+            Item::Array { ref val, .. } => Node::Array(elements(val, src, span), span),
+            Item::AoT(ref tables) => Node::Array(
+                tables
+                    .iter()
+                    .map(|t| Node::new(t, depth, src, src.and_then(|src| header_span(src, t))))
+                    .collect(),
+                span,
+            ),
+            _ => Node::Value(item, span),
+        }
+    }
+
+    /// Returns the span of the node.
+    #[cfg(feature = "serde")]
+    pub(crate) fn span(&self) -> Span {
+        match *self {
+            Node::Value(_, span) | Node::Table(_, span) | Node::Array(_, span) => span,
+        }
+    }
+}
+
+/// Returns the entries of `container` by name, the headers of its tables
+/// having `depth` segments in front of the names.
+pub(crate) fn entries<'b, 'a: 'b>(
+    container: &'b Container<'a>,
+    depth: usize,
+    src: Option<&str>,
+) -> Vec<(String, Node<'b, 'a>)> {
+    let mut out = Vec::new();
+    for (k, v) in container.iter() {
+        let names: Vec<String> = k.segments().map(|s| s.value.into_owned()).collect();
+        let span = src.and_then(|src| entry_span(src, k, v));
+        let node = Node::new(v, names.len(), src, span);
+        let names = match *v {
+            Item::Table { .. } | Item::AoT(_) if depth < names.len() => &names[depth..],
+            Item::Table { .. } | Item::AoT(_) => &names[names.len() - 1..],
+            _ => &names[..],
+        };
+        insert(&mut out, names, node);
+    }
+    out
+}
+
+/// Returns the nodes of the values of an array whose text is at `span`.
+fn elements<'b, 'a: 'b>(val: &'b [Item<'a>], src: Option<&str>, span: Span) -> Vec<Node<'b, 'a>> {
+    let mut cursor = span.map(|(start, _)| start + 1);
+    let mut nodes = Vec::new();
+    for v in val {
+        let len = match *v {
+            Item::WS(s) => s.len(),
+            Item::Comment(ref t) => t.comment_ws.len() + t.comment.len() + t.trail.len(),
+            _ => {
+                let span = match (src, cursor) {
+                    (Some(src), Some(start)) => Some((start, end_of(src, start, v))),
+                    _ => None,
+                };
+                nodes.push(Node::new(v, 0, src, span));
+                cursor = span.map(|(_, end)| end);
+                continue;
+            }
+        };
+        cursor = cursor.map(|c| c + len);
+    }
+    nodes
+}
+
+/// Returns the byte offset of `s` in `src`, if it is a slice of it.
+fn offset(src: &str, s: &str) -> Option<usize> {
+    let start = src.as_ptr() as usize;
+    let ptr = s.as_ptr() as usize;
+    if ptr >= start && ptr + s.len() <= start + src.len() {
+        Some(ptr - start)
+    } else {
+        None
+    }
+}
+
+/// Returns the span of the entry `key` of a container: its value, or the
+/// header of a table, or the headers of an AoT.
+fn entry_span(src: &str, key: &Key, item: &Item) -> Span {
+    match *item {
+        Item::Table { .. } => header_span(src, item),
+        Item::AoT(ref tables) => {
+            let (start, _) = header_span(src, tables.first()?)?;
+            let (_, end) = header_span(src, tables.last()?)?;
+            Some((start, end))
+        }
+        _ => {
+            let start = offset(src, key.sep)? + key.sep.len();
+            Some((start, end_of(src, start, item)))
+        }
+    }
+}
+
+/// Returns the span of the header of a table.
+fn header_span(src: &str, table: &Item) -> Span {
+    let (indent, close) = match *table {
+        Item::Table {
+            ref trivia,
+            is_aot_elem,
+            ..
+        } => (trivia.indent, if is_aot_elem { "]]" } else { "]" }),
+        _ => return None,
+    };
+    let start = offset(src, indent)? + indent.len();
+    if !src[start..].starts_with('[') {
+        return None;
+    }
+    let end = start + src[start..].find(close)? + close.len();
+    Some((start, end))
+}
+
+/// Returns the offset right after the text of the value `item`, which
+/// starts at `start` in `src`.
+fn end_of(src: &str, start: usize, item: &Item) -> usize {
+    match *item {
+        Item::Array { ref val, .. } => {
+            let mut cursor = start + 1;
+            for v in val {
+                cursor = match *v {
+                    Item::WS(s) => cursor + s.len(),
+                    Item::Comment(ref t) => {
+                        cursor + t.comment_ws.len() + t.comment.len() + t.trail.len()
+                    }
+                    _ => end_of(src, cursor, v),
+                };
+            }
+            cursor + 1
+        }
+        // Nothing can come between the last value and the closing brace.
+        Item::InlineTable { ref val, .. } => match val.body.last() {
+            Some(&(Some(ref key), ref v)) => match offset(src, key.sep) {
+                Some(sep) => end_of(src, sep + key.sep.len(), v) + 1,
+                None => start + item.as_string().len(),
+            },
+            _ => start + 2,
+        },
+        _ => start + item.as_string().len(),
+    }
+}
+
+/// Returns the number of segments that the names of the tables under the
+/// `[table]` or AoT `item` have in front of their own, for an item whose
+/// header is unknown, or `None` if it has no such tables.
+///
+/// The names only show the prefix they share: when all of them go through
+/// the same implicit table, it is taken as part of the header.
+pub(crate) fn header_depth(item: &Item) -> Option<usize> {
+    let tables: Vec<&Container> = match *item {
+        Item::Table { ref val, .. } => vec![val],
+        Item::AoT(ref tables) => tables.iter().filter_map(|t| t.as_table().ok()).collect(),
+        _ => return None,
+    };
+    let mut prefix: Option<Vec<String>> = None;
+    let mut shortest = usize::MAX;
+    let children = tables.iter().flat_map(|t| t.iter()).filter(|e| is_table_like(e.1));
+    for (key, _) in children {
+        let names: Vec<String> = key.segments().map(|s| s.value.into_owned()).collect();
+        shortest = shortest.min(names.len() - 1);
+        prefix = Some(match prefix {
+            Some(prefix) => prefix
+                .into_iter()
+                .zip(names)
+                .take_while(|(a, b)| a == b)
+                .map(|p| p.0)
+                .collect(),
+            None => names,
+        });
+    }
+    prefix.map(|prefix| prefix.len().min(shortest))
+}
+
+/// Inserts `node` under the dotted name `names`, merging it with the
+/// table or AoT already there.
+fn insert<'b, 'a: 'b>(
+    out: &mut Vec<(String, Node<'b, 'a>)>,
+    names: &[String],
+    node: Node<'b, 'a>,
+) {
+    let (name, rest) = match names.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let pos = match out.iter().position(|entry| entry.0 == *name) {
+        Some(pos) => pos,
+        None if rest.is_empty() => return out.push((name.clone(), node)),
+        None => {
+            out.push((name.clone(), Node::Table(Vec::new(), None)));
+            out.len() - 1
+        }
+    };
+    let existing = &mut out[pos].1;
+    if !rest.is_empty() {
+        match *existing {
+            Node::Table(ref mut entries, _) => insert(entries, rest, node),
+            Node::Array(ref mut tables, _) => {
+                if let Some(&mut Node::Table(ref mut entries, _)) = tables.last_mut() {
+                    insert(entries, rest, node);
+                }
+            }
+            Node::Value(..) => {}
+        }
+        return;
+    }
+    match (existing, node) {
+        (&mut Node::Table(ref mut entries, _), Node::Table(new, _)) => {
+            for (name, node) in new {
+                insert(entries, &[name], node);
+            }
+        }
+        (&mut Node::Array(ref mut tables, _), Node::Array(new, _)) => tables.extend(new),
+        (existing, node) => *existing = node,
+    }
+}
+
+/// Returns the node of an item whose header is unknown.
+#[cfg(feature = "serde")]
+pub(crate) fn item_node<'b, 'a>(item: &'b Item<'a>) -> Node<'b, 'a> {
+    Node::new(item, header_depth(item).unwrap_or(0), None, None)
+}
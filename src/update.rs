@@ -0,0 +1,567 @@
+//! Updating a document from a serde value, keeping its formatting.
+//!
+//! This module is available with the `serde` feature. The value is
+//! serialized as with `ser::to_document`, and the result is merged into the
+//! document rather than replacing it:
+//!
+//! - values that didn't change are left alone, and the others are swapped
+//!   with `Item::set_value`, which keeps their comments and string quotes;
+//! - tables and AoTs are merged entry by entry, and a table stays inline or
+//!   not as it is in the document;
+//! - new key/values go after the last key/value of their table, and new
+//!   tables after its last child table;
+//! - the entries the value doesn't have are kept, unless
+//!   `UpdateOptions::remove_missing` is set.
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate Molten;
+//!
+//! use Molten::parser::Parser;
+//!
+//! #[derive(Serialize)]
+//! struct Config {
+//!     name: &'static str,
+//!     retries: u32,
+//! }
+//!
+//! # fn main() {
+//! let src = "# Settings\nname = 'app'  # shown to users\nretries = 3\n";
+//! let mut doc = Parser::new(src).parse().unwrap();
+//! let mut buf = String::new();
+//! let config = Config { name: "app", retries: 5 };
+//! Molten::update::update_from(&mut doc, &config, &mut buf).unwrap();
+//! assert_eq!(
+//!     doc.as_string(),
+//!     "# Settings\nname = 'app'  # shown to users\nretries = 5\n"
+//! );
+//! # }
+//! ```
+//!
+//! The text of the new items is kept in the buffer given to each update,
+//! which the document borrows for as long as it lives. A document updated
+//! more than once takes a buffer for each update, or is written out and
+//! parsed again:
+//!
+//! ```
+//! # #[macro_use]
+//! # extern crate serde_derive;
+//! # extern crate Molten;
+//! # use Molten::parser::Parser;
+//! # #[derive(Serialize)]
+//! # struct Config {
+//! #     name: &'static str,
+//! #     retries: u32,
+//! # }
+//! # fn main() {
+//! let (mut first, mut second) = (String::new(), String::new());
+//! let mut doc = Parser::new("name = 'app'\nretries = 3\n").parse().unwrap();
+//! let config = Config { name: "app", retries: 5 };
+//! Molten::update::update_from(&mut doc, &config, &mut first).unwrap();
+//! let config = Config { name: "cli", retries: 5 };
+//! Molten::update::update_from(&mut doc, &config, &mut second).unwrap();
+//! assert_eq!(doc.as_string(), "name = 'cli'\nretries = 5\n");
+//!
+//! let (text, mut buf) = (doc.as_string(), String::new());
+//! let mut doc = Parser::new(&text).parse().unwrap();
+//! let config = Config { name: "cli", retries: 7 };
+//! Molten::update::update_from(&mut doc, &config, &mut buf).unwrap();
+//! assert_eq!(doc.as_string(), "name = 'cli'\nretries = 7\n");
+//! # }
+//! ```
+
+use serde::ser::Serialize;
+
+use items::*;
+use errors::*;
+use container::{is_table_like, Container, RemoveOptions};
+use parser::Parser;
+use section::Section;
+use ser::{to_entries, write_table, Value};
+use tomldoc::TOMLDocument;
+
+/// The entries `update_with` takes out of the document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UpdateOptions {
+    /// Remove the entries that the value doesn't have, along with the
+    /// comment lines directly above them.
+    pub remove_missing: bool,
+}
+
+/// Updates `doc` so that it holds `value`, keeping its formatting.
+///
+/// The text of the new items is kept in `buf`, which must outlive the
+/// document: each update of the same document takes a buffer of its own.
+pub fn update_from<'a, T: Serialize + ?Sized>(
+    doc: &mut TOMLDocument<'a>,
+    value: &T,
+    buf: &'a mut String,
+) -> Result<()> {
+    update_with(doc, value, buf, UpdateOptions::default())
+}
+
+/// Updates `doc` so that it holds `value`, as selected by `options`.
+pub fn update_with<'a, T: Serialize + ?Sized>(
+    doc: &mut TOMLDocument<'a>,
+    value: &T,
+    buf: &'a mut String,
+    options: UpdateOptions,
+) -> Result<()> {
+    let mut entries = to_entries(value)?;
+    shape(doc, &[], true, &mut entries);
+    buf.clear();
+    write_table(buf, "", &entries, false);
+    let src: &'a str = buf;
+    let fresh = Parser::new(src).parse()?;
+    merge(doc, &[], true, fresh, options)
+}
+
+/// Where the child of a table is found in a container.
+enum Found {
+    /// At this position of the body.
+    At(usize),
+    /// Only in the names of the headers of its own child tables, as for
+    /// `[a.b]` without `[a]`.
+    Implicit,
+    Missing,
+}
+
+/// Returns the decoded segments of `key`.
+fn segments(key: &Key) -> Vec<String> {
+    key.segments().map(|s| s.value.into_owned()).collect()
+}
+
+/// Returns `path` followed by `name`.
+fn join(path: &[String], name: &str) -> Vec<String> {
+    let mut path = path.to_vec();
+    path.push(name.to_string());
+    path
+}
+
+/// Returns the name of the child of the table at `path` that the entry
+/// `key` belongs to, and whether the entry is that child itself.
+///
+/// The container is the body of the table if `own` is set, or that of a
+/// parent holding the headers of its child tables otherwise.
+fn child_name(key: &Key, path: &[String], own: bool) -> Option<(String, bool)> {
+    let mut segments = segments(key);
+    if own && segments.len() == 1 {
+        return segments.pop().map(|name| (name, true));
+    }
+    if segments.len() > path.len() && segments[..path.len()] == *path {
+        let exact = segments.len() == path.len() + 1;
+        return Some((segments.swap_remove(path.len()), exact));
+    }
+    None
+}
+
+/// Looks for the child `name` of the table at `path` in `c`.
+fn find(c: &Container, path: &[String], own: bool, name: &str) -> Found {
+    let mut found = Found::Missing;
+    for (pos, entry) in c.body.iter().enumerate() {
+        let key = match entry.0 {
+            Some(ref key) => key,
+            None => continue,
+        };
+        match child_name(key, path, own) {
+            Some((ref n, true)) if n == name => return Found::At(pos),
+            Some((ref n, false)) if n == name => found = Found::Implicit,
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Makes the tables of `entries` inline or not as the matching ones are in
+/// the document, so that the serialized text follows its layout.
+fn shape(c: &Container, path: &[String], own: bool, entries: &mut [(String, Value)]) {
+    for (name, value) in entries {
+        match find(c, path, own, name) {
+            Found::At(pos) => shape_item(&c.body[pos].1, &join(path, name), value),
+            Found::Implicit => if let Value::Table {
+                ref mut entries,
+                ref mut inline,
+            } = *value
+            {
+                *inline = false;
+                shape(c, &join(path, name), false, entries);
+            },
+            Found::Missing => {}
+        }
+    }
+}
+
+/// Shapes `value` after the item of the document it replaces.
+fn shape_item(item: &Item, path: &[String], value: &mut Value) {
+    match (item, value) {
+        (Item::Table { val, .. }, Value::Table { entries, inline }) => {
+            *inline = false;
+            shape(val, path, true, entries);
+        }
+        (Item::AoT(tables), Value::Array(values)) => {
+            for (pos, value) in values.iter_mut().enumerate() {
+                match tables.get(pos) {
+                    Some(table) => shape_item(table, path, value),
+                    None => if let Value::Table { ref mut inline, .. } = *value {
+                        *inline = false;
+                    },
+                }
+            }
+        }
+        (&Item::Table { .. }, _) | (&Item::AoT(_), _) => {}
+        (_, &mut Value::Array(ref mut values)) => for value in values {
+            if let Value::Table { ref mut inline, .. } = *value {
+                *inline = true;
+            }
+        },
+        (_, &mut Value::Table { ref mut inline, .. }) => *inline = true,
+        _ => {}
+    }
+}
+
+/// Merges the entries of `fresh` into `c`, where the table at `path` is
+/// found as described by `child_name`.
+fn merge<'a>(
+    c: &mut Container<'a>,
+    path: &[String],
+    own: bool,
+    fresh: Container<'a>,
+    options: UpdateOptions,
+) -> Result<()> {
+    let mut names = Vec::new();
+    for (key, item) in fresh {
+        let name = segments(&key).pop().unwrap_or_default();
+        match find(c, path, own, &name) {
+            Found::At(pos) => merge_item(c, pos, &join(path, &name), key, item, options)?,
+            Found::Implicit => merge_implicit(c, path, own, &name, key, item, options)?,
+            Found::Missing => add(c, key, item)?,
+        }
+        names.push(name);
+    }
+
+    if options.remove_missing {
+        let missing: Vec<Key<'a>> = c.body
+            .iter()
+            .filter_map(|entry| entry.0.as_ref())
+            .filter(|key| match child_name(key, path, own) {
+                Some((name, _)) => !names.contains(&name),
+                None => false,
+            })
+            .cloned()
+            .collect();
+        for key in &missing {
+            remove(c, key)?;
+        }
+    }
+    Ok(())
+}
+
+/// Merges `item` into the entry at `pos` in `c`.
+fn merge_item<'a>(
+    c: &mut Container<'a>,
+    pos: usize,
+    path: &[String],
+    key: Key<'a>,
+    item: Item<'a>,
+    options: UpdateOptions,
+) -> Result<()> {
+    let item = as_datetime(&c.body[pos].1, item);
+    let old = &c.body[pos].1;
+    if old.discriminant() != item.discriminant() && (is_table_like(old) || is_table_like(&item)) {
+        // A table turned into a value or the other way around moves to
+        // where its new kind belongs.
+        let old = c.body[pos].0.clone().unwrap();
+        remove(c, &old)?;
+        return add(c, key, item);
+    }
+
+    match (&mut c.body[pos].1, item) {
+        (&mut Item::Table { val: ref mut old, .. }, Item::Table { val, .. }) |
+        (&mut Item::InlineTable { val: ref mut old, .. }, Item::InlineTable { val, .. }) => {
+            merge(old, path, true, val, options)
+        }
+        (&mut Item::AoT(ref mut old), Item::AoT(tables)) => {
+            let len = tables.len();
+            for (idx, table) in tables.into_iter().enumerate() {
+                match (old.get_mut(idx), table) {
+                    (Some(&mut Item::Table { val: ref mut old, .. }), Item::Table { val, .. }) => {
+                        merge(old, path, true, val, options)?
+                    }
+                    (_, mut table) => {
+                        trim_end(&mut table);
+                        ::aot::insert(old, idx, table)?
+                    }
+                }
+            }
+            while old.len() > len {
+                let last = old.len() - 1;
+                ::aot::remove(old, last)?;
+            }
+            Ok(())
+        }
+        (old, item) => {
+            if !same(old, &item) {
+                old.set_value(item)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Merges `item` into the table `name` of the table at `path`, which
+/// only exists through the headers of its child tables.
+fn merge_implicit<'a>(
+    c: &mut Container<'a>,
+    path: &[String],
+    own: bool,
+    name: &str,
+    key: Key<'a>,
+    item: Item<'a>,
+    options: UpdateOptions,
+) -> Result<()> {
+    let (val, trivia) = match item {
+        Item::Table { val, trivia, .. } => (val, trivia),
+        item => {
+            let children: Vec<Key<'a>> = c.body
+                .iter()
+                .filter_map(|entry| entry.0.as_ref())
+                .filter(|key| child_name(key, path, own).is_some_and(|(n, _)| n == name))
+                .cloned()
+                .collect();
+            for child in &children {
+                remove(c, child)?;
+            }
+            return add(c, key, item);
+        }
+    };
+
+    // The key/values of the table need a header of their own.
+    let mut values = Container::new();
+    let mut tables = Container::new();
+    for (k, v) in val {
+        if is_table_like(&v) {
+            tables.append(k, v)?;
+        } else {
+            values.append(k, v)?;
+        }
+    }
+    if !values.is_empty() {
+        let table = Item::Table {
+            is_aot_elem: false,
+            val: values,
+            trivia,
+        };
+        add(c, key, table)?;
+    }
+    merge(c, &join(path, name), false, tables, options)
+}
+
+/// Returns `item` as a datetime if `old` is one and the string `item`
+/// reads as one, since serde writes them as strings.
+fn as_datetime<'a>(old: &Item<'a>, item: Item<'a>) -> Item<'a> {
+    if let (&Item::DateTime { .. }, &Item::Str { original, .. }) = (old, &item) {
+        if let Ok(datetime @ Item::DateTime { .. }) = ::api::value(original) {
+            return datetime;
+        }
+    }
+    item
+}
+
+/// Inserts a new entry where it belongs in `c`.
+fn add<'a>(c: &mut Container<'a>, mut key: Key<'a>, mut item: Item<'a>) -> Result<()> {
+    if is_table_like(&item) {
+        trim_end(&mut item);
+        return c.insert_section(Section {
+            key,
+            item,
+            comments: Vec::new(),
+            gap: vec![::NL],
+        });
+    }
+
+    // Follow the spacing of the last key/value.
+    let last = c.body
+        .iter()
+        .rev()
+        .filter(|entry| entry.1.is_value() && !is_table_like(&entry.1))
+        .find_map(|entry| entry.0.as_ref().map(|k| (k.sep, entry.1.trivia().indent)));
+    if let Some((sep, indent)) = last {
+        key.sep = sep;
+        item.trivia_mut().indent = indent;
+    }
+    c.insert(key, item)
+}
+
+/// Removes the entry `key` of `c`, along with its comments.
+fn remove<'a>(c: &mut Container<'a>, key: &Key<'a>) -> Result<()> {
+    if is_table_like(&c.body[c.position(key)?].1) {
+        c.take_section(key)?;
+    } else {
+        let options = RemoveOptions {
+            comments: true,
+            whitespace: true,
+        };
+        c.remove_with(key, options)?;
+    }
+    Ok(())
+}
+
+/// Removes the blank lines at the end of a new table, which set it apart
+/// from the table that followed it in the serialized text.
+fn trim_end(item: &mut Item) {
+    let tail = match *item {
+        Item::Table { ref mut val, .. } => ::aot::tail_mut(val),
+        Item::AoT(ref mut tables) => {
+            for table in tables {
+                trim_end(table);
+            }
+            return;
+        }
+        _ => return,
+    };
+    while let Some(&(None, Item::WS(_))) = tail.body.last() {
+        tail.body.pop();
+    }
+}
+
+/// Returns `true` if two values are equal, however they are written.
+fn same(a: &Item, b: &Item) -> bool {
+    match (a, b) {
+        (&Item::Integer { val: x, .. }, &Item::Integer { val: y, .. }) => x == y,
+        (&Item::Float { val: x, .. }, &Item::Float { val: y, .. }) => {
+            x == y || x.is_nan() && y.is_nan()
+        }
+        (&Item::Bool { val: x, .. }, &Item::Bool { val: y, .. }) => x == y,
+        (&Item::DateTime { val: x, .. }, &Item::DateTime { val: y, .. }) => x == y,
+        (&Item::Str { .. }, &Item::Str { .. }) => a.as_str().ok() == b.as_str().ok(),
+        (Item::Array { val: x, .. }, Item::Array { val: y, .. }) => {
+            let x: Vec<_> = x.iter().filter(|v| v.is_value()).collect();
+            let y: Vec<_> = y.iter().filter(|v| v.is_value()).collect();
+            x.len() == y.len() && x.iter().zip(&y).all(|(v, w)| same(v, w))
+        }
+        (Item::InlineTable { val: x, .. }, Item::InlineTable { val: y, .. }) => {
+            x.len() == y.len() && x.iter().all(|(k, v)| match y.map.get(k) {
+                Some(&pos) => same(v, &y.body[pos].1),
+                None => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use parser::Parser;
+    use super::{update_from, update_with, UpdateOptions};
+
+    #[derive(Serialize)]
+    struct Server {
+        host: String,
+        port: u16,
+        tls: Option<bool>,
+    }
+
+    #[derive(Serialize)]
+    struct Config {
+        title: String,
+        count: i64,
+        tags: Vec<String>,
+        server: Server,
+    }
+
+    const SRC: &str = "# Config\ntitle = \"old\"   # the title\ncount = 1\n\n\
+                       [server]\nhost = 'localhost'\nport = 80\n";
+
+    fn config() -> Config {
+        Config {
+            title: "new".to_string(),
+            count: 1,
+            tags: vec!["a".to_string()],
+            server: Server {
+                host: "localhost".to_string(),
+                port: 8080,
+                tls: Some(true),
+            },
+        }
+    }
+
+    #[test]
+    fn update() {
+        let mut doc = Parser::new(SRC).parse().unwrap();
+        let mut buf = String::new();
+        update_from(&mut doc, &config(), &mut buf).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "# Config\ntitle = \"new\"   # the title\ncount = 1\ntags = [\"a\"]\n\n\
+             [server]\nhost = 'localhost'\nport = 8080\ntls = true\n"
+        );
+    }
+
+    #[test]
+    fn remove_missing() {
+        #[derive(Serialize)]
+        struct Bin {
+            name: &'static str,
+        }
+
+        #[derive(Serialize)]
+        struct Manifest {
+            bin: Vec<Bin>,
+            deps: BTreeMap<&'static str, &'static str>,
+        }
+
+        let src = "old = 1\n\n# Binaries\n[[bin]]\nname = \"a\"\n\n[[bin]]\nname = \"b\"\n\n\
+                   [deps.serde]\nversion = \"1\"\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let mut buf = String::new();
+        let mut deps = BTreeMap::new();
+        deps.insert("log", "0.4");
+        let manifest = Manifest {
+            bin: vec![Bin { name: "c" }],
+            deps,
+        };
+        let options = UpdateOptions {
+            remove_missing: true,
+        };
+        update_with(&mut doc, &manifest, &mut buf, options).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "# Binaries\n[[bin]]\nname = \"c\"\n\n[deps]\nlog = \"0.4\"\n"
+        );
+    }
+
+    #[test]
+    fn keep_layout() {
+        #[derive(Serialize)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        #[derive(Serialize)]
+        struct Shape {
+            origin: Point,
+            points: Vec<Point>,
+            created: &'static str,
+        }
+
+        let src = "origin = {x = 0, y = 0}\npoints = [{x = 1, y = 1}]\n\
+                   created = 1979-05-27T07:32:00Z\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        let mut buf = String::new();
+        let shape = Shape {
+            origin: Point { x: 0, y: 2 },
+            points: vec![Point { x: 1, y: 1 }],
+            created: "1979-05-27T07:32:00Z",
+        };
+        update_from(&mut doc, &shape, &mut buf).unwrap();
+        assert_eq!(
+            doc.as_string(),
+            "origin = {x = 0, y = 2}\npoints = [{x = 1, y = 1}]\n\
+             created = 1979-05-27T07:32:00Z\n"
+        );
+    }
+}
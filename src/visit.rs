@@ -0,0 +1,624 @@
+//! Visitors over the syntax tree of a document.
+//!
+//! Each method of `Visit` and `VisitMut` has a default that calls the
+//! matching `walk_*` function, which visits the children of the node. An
+//! implementation overrides the methods for the nodes it cares about, and
+//! calls the `walk_*` function from there if it still wants to go deeper.
+//!
+//! Variant methods, such as `visit_integer`, are given the whole `Item`, so
+//! that a `VisitMut` can replace it with another variant. The keys of the
+//! table headers and AoTs go through `visit_table_header`, and the other
+//! keys through `visit_key` only. Trivia is visited before the contents of
+//! the node it belongs to.
+//!
+//! ```
+//! use Molten::items::Item;
+//! use Molten::visit::{walk_table, Visit};
+//!
+//! struct Depth(usize, usize);
+//!
+//! impl<'ast, 'a> Visit<'ast, 'a> for Depth {
+//!     fn visit_table(&mut self, node: &'ast Item<'a>) {
+//!         self.0 += 1;
+//!         self.1 = self.1.max(self.0);
+//!         walk_table(self, node);
+//!         self.0 -= 1;
+//!     }
+//! }
+//!
+//! let doc = Molten::parser::Parser::new("[a]\n[a.b]\nx = 1\n").parse().unwrap();
+//! let mut depth = Depth(0, 0);
+//! depth.visit_container(&doc);
+//! assert_eq!(depth.1, 2);
+//! ```
+
+use items::*;
+use container::Container;
+
+/// A read-only visitor over the syntax tree.
+pub trait Visit<'ast, 'a> {
+    /// Visits the entries of a table body or of the document.
+    fn visit_container(&mut self, node: &'ast Container<'a>) {
+        walk_container(self, node)
+    }
+
+    /// Visits an entry of a container, with its key if it has one.
+    fn visit_entry(&mut self, key: Option<&'ast Key<'a>>, node: &'ast Item<'a>) {
+        walk_entry(self, key, node)
+    }
+
+    /// Visits the key of a table or AoT.
+    fn visit_table_header(&mut self, key: &'ast Key<'a>) {
+        walk_table_header(self, key)
+    }
+
+    /// Visits a key.
+    fn visit_key(&mut self, _key: &'ast Key<'a>) {}
+
+    /// Visits the trivia of a value or a comment.
+    fn visit_trivia(&mut self, _trivia: &'ast Trivia<'a>) {}
+
+    /// Visits any item.
+    fn visit_item(&mut self, node: &'ast Item<'a>) {
+        walk_item(self, node)
+    }
+
+    /// Visits whitespace.
+    fn visit_ws(&mut self, _ws: &'ast str) {}
+
+    /// Visits a comment line.
+    fn visit_comment(&mut self, trivia: &'ast Trivia<'a>) {
+        walk_comment(self, trivia)
+    }
+
+    /// Visits an `Item::Integer`.
+    fn visit_integer(&mut self, node: &'ast Item<'a>) {
+        walk_integer(self, node)
+    }
+
+    /// Visits an `Item::Float`.
+    fn visit_float(&mut self, node: &'ast Item<'a>) {
+        walk_float(self, node)
+    }
+
+    /// Visits an `Item::Bool`.
+    fn visit_bool(&mut self, node: &'ast Item<'a>) {
+        walk_bool(self, node)
+    }
+
+    /// Visits an `Item::DateTime`.
+    fn visit_datetime(&mut self, node: &'ast Item<'a>) {
+        walk_datetime(self, node)
+    }
+
+    /// Visits an `Item::Str`.
+    fn visit_string(&mut self, node: &'ast Item<'a>) {
+        walk_string(self, node)
+    }
+
+    /// Visits an `Item::Array`.
+    fn visit_array(&mut self, node: &'ast Item<'a>) {
+        walk_array(self, node)
+    }
+
+    /// Visits an `Item::Table`.
+    fn visit_table(&mut self, node: &'ast Item<'a>) {
+        walk_table(self, node)
+    }
+
+    /// Visits an `Item::InlineTable`.
+    fn visit_inline_table(&mut self, node: &'ast Item<'a>) {
+        walk_inline_table(self, node)
+    }
+
+    /// Visits an `Item::AoT`.
+    fn visit_aot(&mut self, node: &'ast Item<'a>) {
+        walk_aot(self, node)
+    }
+
+    /// Visits the table at `index` in an AoT.
+    fn visit_aot_entry(&mut self, index: usize, node: &'ast Item<'a>) {
+        walk_aot_entry(self, index, node)
+    }
+
+    /// Visits an `Item::None`.
+    fn visit_none(&mut self, _node: &'ast Item<'a>) {}
+}
+
+/// Visits each entry of `node`.
+pub fn walk_container<'ast, 'a, V>(v: &mut V, node: &'ast Container<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    for (k, item) in &node.body {
+        v.visit_entry(k.as_ref(), item);
+    }
+}
+
+/// Visits the key of the entry, as a table header for tables and AoTs, and
+/// then its item.
+pub fn walk_entry<'ast, 'a, V>(v: &mut V, key: Option<&'ast Key<'a>>, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Some(key) = key {
+        match *node {
+            Item::Table { .. } | Item::AoT(_) => v.visit_table_header(key),
+            _ => v.visit_key(key),
+        }
+    }
+    v.visit_item(node);
+}
+
+/// Visits the key of the header.
+pub fn walk_table_header<'ast, 'a, V>(v: &mut V, key: &'ast Key<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    v.visit_key(key);
+}
+
+/// Calls the visit method of the variant of `node`.
+pub fn walk_item<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    match *node {
+        Item::WS(ws) => v.visit_ws(ws),
+        Item::Comment(ref trivia) => v.visit_comment(trivia),
+        Item::Integer { .. } => v.visit_integer(node),
+        Item::Float { .. } => v.visit_float(node),
+        Item::Bool { .. } => v.visit_bool(node),
+        Item::DateTime { .. } => v.visit_datetime(node),
+        Item::Str { .. } => v.visit_string(node),
+        Item::Array { .. } => v.visit_array(node),
+        Item::Table { .. } => v.visit_table(node),
+        Item::InlineTable { .. } => v.visit_inline_table(node),
+        Item::AoT(_) => v.visit_aot(node),
+        Item::None => v.visit_none(node),
+    }
+}
+
+/// Visits the trivia of the comment.
+pub fn walk_comment<'ast, 'a, V>(v: &mut V, trivia: &'ast Trivia<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    v.visit_trivia(trivia);
+}
+
+/// Visits the trivia of the integer.
+pub fn walk_integer<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Item::Integer { ref trivia, .. } = *node {
+        v.visit_trivia(trivia);
+    }
+}
+
+/// Visits the trivia of the float.
+pub fn walk_float<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Item::Float { ref trivia, .. } = *node {
+        v.visit_trivia(trivia);
+    }
+}
+
+/// Visits the trivia of the boolean.
+pub fn walk_bool<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Item::Bool { ref trivia, .. } = *node {
+        v.visit_trivia(trivia);
+    }
+}
+
+/// Visits the trivia of the datetime.
+pub fn walk_datetime<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Item::DateTime { ref trivia, .. } = *node {
+        v.visit_trivia(trivia);
+    }
+}
+
+/// Visits the trivia of the string.
+pub fn walk_string<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Item::Str { ref trivia, .. } = *node {
+        v.visit_trivia(trivia);
+    }
+}
+
+/// Visits the trivia of the array, and then its items.
+pub fn walk_array<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Item::Array { ref val, ref trivia } = *node {
+        v.visit_trivia(trivia);
+        for item in val {
+            v.visit_item(item);
+        }
+    }
+}
+
+/// Visits the trivia of the table header, and then the table body.
+pub fn walk_table<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Item::Table { ref val, ref trivia, .. } = *node {
+        v.visit_trivia(trivia);
+        v.visit_container(val);
+    }
+}
+
+/// Visits the trivia of the inline table, and then its entries.
+pub fn walk_inline_table<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Item::InlineTable { ref val, ref trivia } = *node {
+        v.visit_trivia(trivia);
+        v.visit_container(val);
+    }
+}
+
+/// Visits each table of the AoT.
+pub fn walk_aot<'ast, 'a, V>(v: &mut V, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    if let Item::AoT(ref tables) = *node {
+        for (index, table) in tables.iter().enumerate() {
+            v.visit_aot_entry(index, table);
+        }
+    }
+}
+
+/// Visits the table of the AoT entry.
+pub fn walk_aot_entry<'ast, 'a, V>(v: &mut V, _index: usize, node: &'ast Item<'a>)
+where
+    V: Visit<'ast, 'a> + ?Sized,
+{
+    v.visit_item(node);
+}
+
+/// A visitor over the syntax tree that can change the nodes in place.
+///
+/// Keys can be changed too: the lookup map of each container is rebuilt
+/// once its entries have been visited.
+pub trait VisitMut<'a> {
+    /// Visits the entries of a table body or of the document.
+    fn visit_container_mut(&mut self, node: &mut Container<'a>) {
+        walk_container_mut(self, node)
+    }
+
+    /// Visits an entry of a container, with its key if it has one.
+    fn visit_entry_mut(&mut self, key: Option<&mut Key<'a>>, node: &mut Item<'a>) {
+        walk_entry_mut(self, key, node)
+    }
+
+    /// Visits the key of a table or AoT.
+    fn visit_table_header_mut(&mut self, key: &mut Key<'a>) {
+        walk_table_header_mut(self, key)
+    }
+
+    /// Visits a key.
+    fn visit_key_mut(&mut self, _key: &mut Key<'a>) {}
+
+    /// Visits the trivia of a value or a comment.
+    fn visit_trivia_mut(&mut self, _trivia: &mut Trivia<'a>) {}
+
+    /// Visits any item.
+    fn visit_item_mut(&mut self, node: &mut Item<'a>) {
+        walk_item_mut(self, node)
+    }
+
+    /// Visits whitespace.
+    fn visit_ws_mut(&mut self, _ws: &mut &'a str) {}
+
+    /// Visits a comment line.
+    fn visit_comment_mut(&mut self, trivia: &mut Trivia<'a>) {
+        walk_comment_mut(self, trivia)
+    }
+
+    /// Visits an `Item::Integer`.
+    fn visit_integer_mut(&mut self, node: &mut Item<'a>) {
+        walk_integer_mut(self, node)
+    }
+
+    /// Visits an `Item::Float`.
+    fn visit_float_mut(&mut self, node: &mut Item<'a>) {
+        walk_float_mut(self, node)
+    }
+
+    /// Visits an `Item::Bool`.
+    fn visit_bool_mut(&mut self, node: &mut Item<'a>) {
+        walk_bool_mut(self, node)
+    }
+
+    /// Visits an `Item::DateTime`.
+    fn visit_datetime_mut(&mut self, node: &mut Item<'a>) {
+        walk_datetime_mut(self, node)
+    }
+
+    /// Visits an `Item::Str`.
+    fn visit_string_mut(&mut self, node: &mut Item<'a>) {
+        walk_string_mut(self, node)
+    }
+
+    /// Visits an `Item::Array`.
+    fn visit_array_mut(&mut self, node: &mut Item<'a>) {
+        walk_array_mut(self, node)
+    }
+
+    /// Visits an `Item::Table`.
+    fn visit_table_mut(&mut self, node: &mut Item<'a>) {
+        walk_table_mut(self, node)
+    }
+
+    /// Visits an `Item::InlineTable`.
+    fn visit_inline_table_mut(&mut self, node: &mut Item<'a>) {
+        walk_inline_table_mut(self, node)
+    }
+
+    /// Visits an `Item::AoT`.
+    fn visit_aot_mut(&mut self, node: &mut Item<'a>) {
+        walk_aot_mut(self, node)
+    }
+
+    /// Visits the table at `index` in an AoT.
+    fn visit_aot_entry_mut(&mut self, index: usize, node: &mut Item<'a>) {
+        walk_aot_entry_mut(self, index, node)
+    }
+
+    /// Visits an `Item::None`.
+    fn visit_none_mut(&mut self, _node: &mut Item<'a>) {}
+}
+
+/// Visits each entry of `node`, and then rebuilds its lookup map.
+pub fn walk_container_mut<'a, V>(v: &mut V, node: &mut Container<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    for (k, item) in &mut node.body {
+        v.visit_entry_mut(k.as_mut(), item);
+    }
+    node.rebuild_map();
+}
+
+/// Visits the key of the entry, as a table header for tables and AoTs, and
+/// then its item.
+pub fn walk_entry_mut<'a, V>(v: &mut V, key: Option<&mut Key<'a>>, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Some(key) = key {
+        match *node {
+            Item::Table { .. } | Item::AoT(_) => v.visit_table_header_mut(key),
+            _ => v.visit_key_mut(key),
+        }
+    }
+    v.visit_item_mut(node);
+}
+
+/// Visits the key of the header.
+pub fn walk_table_header_mut<'a, V>(v: &mut V, key: &mut Key<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    v.visit_key_mut(key);
+}
+
+/// Calls the visit method of the variant of `node`.
+pub fn walk_item_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    match *node {
+        Item::WS(ref mut ws) => v.visit_ws_mut(ws),
+        Item::Comment(ref mut trivia) => v.visit_comment_mut(trivia),
+        Item::Integer { .. } => v.visit_integer_mut(node),
+        Item::Float { .. } => v.visit_float_mut(node),
+        Item::Bool { .. } => v.visit_bool_mut(node),
+        Item::DateTime { .. } => v.visit_datetime_mut(node),
+        Item::Str { .. } => v.visit_string_mut(node),
+        Item::Array { .. } => v.visit_array_mut(node),
+        Item::Table { .. } => v.visit_table_mut(node),
+        Item::InlineTable { .. } => v.visit_inline_table_mut(node),
+        Item::AoT(_) => v.visit_aot_mut(node),
+        Item::None => v.visit_none_mut(node),
+    }
+}
+
+/// Visits the trivia of the comment.
+pub fn walk_comment_mut<'a, V>(v: &mut V, trivia: &mut Trivia<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    v.visit_trivia_mut(trivia);
+}
+
+/// Visits the trivia of the integer.
+pub fn walk_integer_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Item::Integer { ref mut trivia, .. } = *node {
+        v.visit_trivia_mut(trivia);
+    }
+}
+
+/// Visits the trivia of the float.
+pub fn walk_float_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Item::Float { ref mut trivia, .. } = *node {
+        v.visit_trivia_mut(trivia);
+    }
+}
+
+/// Visits the trivia of the boolean.
+pub fn walk_bool_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Item::Bool { ref mut trivia, .. } = *node {
+        v.visit_trivia_mut(trivia);
+    }
+}
+
+/// Visits the trivia of the datetime.
+pub fn walk_datetime_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Item::DateTime { ref mut trivia, .. } = *node {
+        v.visit_trivia_mut(trivia);
+    }
+}
+
+/// Visits the trivia of the string.
+pub fn walk_string_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Item::Str { ref mut trivia, .. } = *node {
+        v.visit_trivia_mut(trivia);
+    }
+}
+
+/// Visits the trivia of the array, and then its items.
+pub fn walk_array_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Item::Array { ref mut val, ref mut trivia } = *node {
+        v.visit_trivia_mut(trivia);
+        for item in val {
+            v.visit_item_mut(item);
+        }
+    }
+}
+
+/// Visits the trivia of the table header, and then the table body.
+pub fn walk_table_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Item::Table { ref mut val, ref mut trivia, .. } = *node {
+        v.visit_trivia_mut(trivia);
+        v.visit_container_mut(val);
+    }
+}
+
+/// Visits the trivia of the inline table, and then its entries.
+pub fn walk_inline_table_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Item::InlineTable { ref mut val, ref mut trivia } = *node {
+        v.visit_trivia_mut(trivia);
+        v.visit_container_mut(val);
+    }
+}
+
+/// Visits each table of the AoT.
+pub fn walk_aot_mut<'a, V>(v: &mut V, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    if let Item::AoT(ref mut tables) = *node {
+        for (index, table) in tables.iter_mut().enumerate() {
+            v.visit_aot_entry_mut(index, table);
+        }
+    }
+}
+
+/// Visits the table of the AoT entry.
+pub fn walk_aot_entry_mut<'a, V>(v: &mut V, _index: usize, node: &mut Item<'a>)
+where
+    V: VisitMut<'a> + ?Sized,
+{
+    v.visit_item_mut(node);
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+    use api::*;
+    use super::*;
+
+    #[derive(Default)]
+    struct Collect {
+        keys: Vec<String>,
+        headers: Vec<String>,
+        comments: Vec<String>,
+    }
+
+    impl<'ast, 'a> Visit<'ast, 'a> for Collect {
+        fn visit_key(&mut self, key: &'ast Key<'a>) {
+            self.keys.push(key.key.to_string());
+        }
+
+        fn visit_table_header(&mut self, key: &'ast Key<'a>) {
+            self.headers.push(key.key.to_string());
+        }
+
+        fn visit_trivia(&mut self, trivia: &'ast Trivia<'a>) {
+            if !trivia.comment.is_empty() {
+                self.comments.push(trivia.comment.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn visit() {
+        let src = "# top\na = {b = 1} # inline\n[t] # header\nc = [2, 3]\n[[u]]\nd = 4\n";
+        let doc = Parser::new(src).parse().unwrap();
+        let mut collect = Collect::default();
+        collect.visit_container(&doc);
+        assert_eq!(collect.keys, vec!["a", "b", "c", "d"]);
+        assert_eq!(collect.headers, vec!["t", "u"]);
+        assert_eq!(collect.comments, vec!["# top", "# inline", "# header"]);
+    }
+
+    struct Redact;
+
+    impl<'a> VisitMut<'a> for Redact {
+        fn visit_key_mut(&mut self, key: &mut Key<'a>) {
+            if key.key == "pwd" {
+                key.key = "password".into();
+            }
+        }
+
+        fn visit_string_mut(&mut self, node: &mut Item<'a>) {
+            node.set_value(string("\"***\"").unwrap()).unwrap();
+            walk_string_mut(self, node);
+        }
+
+        fn visit_trivia_mut(&mut self, trivia: &mut Trivia<'a>) {
+            trivia.comment_ws = "";
+            trivia.comment = "";
+        }
+    }
+
+    #[test]
+    fn visit_mut() {
+        let src = "user = \"me\" # who\n[db]\npwd = 'secret'\nport = 1\n";
+        let mut doc = Parser::new(src).parse().unwrap();
+        Redact.visit_container_mut(&mut doc);
+        assert_eq!(doc.as_string(), "user = \"***\"\n[db]\npassword = '***'\nport = 1\n");
+        assert_eq!(doc["db"]["password"].as_string(), "'***'");
+    }
+}
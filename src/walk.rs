@@ -0,0 +1,263 @@
+//! Depth-first walking of a document tree.
+//!
+//! A `Walker` goes over every node below a container or item, together with
+//! the path leading to it. Table headers are stored under their full dotted
+//! name, so below `[a]`, the path of `c` in `[a.b]` is
+//! `[Key("a"), Key("a.b"), Key("c")]`.
+//! Whitespace and comments are skipped unless asked for, and are given the
+//! path of the table or array they are in.
+
+use std::fmt;
+
+use items::*;
+use container::Container;
+
+/// A step in the path from the root of a walk to a node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment<'b, 'a: 'b> {
+    /// The key of a table entry.
+    Key(&'b Key<'a>),
+    /// The index of a value in an array, or of a table in an AoT.
+    Index(usize),
+}
+
+/// The order in which a `Walker` yields nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    /// Each node comes before its children.
+    Pre,
+    /// Each node comes after its children.
+    Post,
+}
+
+/// A pending step of the walk.
+enum Step<'b, 'a: 'b> {
+    /// The node hasn't been looked at yet.
+    Enter(Vec<Segment<'b, 'a>>, &'b Item<'a>),
+    /// The children of the node have been walked, in post-order.
+    Leave(Vec<Segment<'b, 'a>>, &'b Item<'a>),
+}
+
+/// A predicate telling a `Walker` to skip the children of a node.
+type Prune<'b, 'a> = Box<dyn FnMut(&[Segment<'b, 'a>], &Item<'a>) -> bool + 'b>;
+
+/// A depth-first iterator over the nodes of a document tree, yielding the
+/// path to each node along with the node.
+pub struct Walker<'b, 'a: 'b> {
+    stack: Vec<Step<'b, 'a>>,
+    order: Order,
+    trivia: bool,
+    prune: Option<Prune<'b, 'a>>,
+}
+
+impl<'b, 'a: 'b> Walker<'b, 'a> {
+    /// Returns a walker in pre-order, without trivia, that starts from
+    /// `roots`.
+    fn new(roots: Vec<Step<'b, 'a>>) -> Walker<'b, 'a> {
+        let mut stack = roots;
+        stack.reverse();
+        Walker {
+            stack,
+            order: Order::Pre,
+            trivia: false,
+            prune: None,
+        }
+    }
+
+    /// Sets the order in which nodes are yielded.
+    pub fn order(mut self, order: Order) -> Walker<'b, 'a> {
+        self.order = order;
+        self
+    }
+
+    /// Sets whether whitespace and comments are yielded.
+    pub fn trivia(mut self, trivia: bool) -> Walker<'b, 'a> {
+        self.trivia = trivia;
+        self
+    }
+
+    /// Skips the children of the nodes for which `prune` returns `true`.
+    /// The nodes themselves are still yielded.
+    pub fn prune<F>(mut self, prune: F) -> Walker<'b, 'a>
+    where
+        F: FnMut(&[Segment<'b, 'a>], &Item<'a>) -> bool + 'b,
+    {
+        self.prune = Some(Box::new(prune));
+        self
+    }
+
+    /// Pushes the children of `item` on the stack, the first one on top.
+    fn push_children(&mut self, path: &[Segment<'b, 'a>], item: &'b Item<'a>) {
+        let mut children = Vec::new();
+        match *item {
+            Item::Table { ref val, .. } | Item::InlineTable { ref val, .. } => {
+                children.extend(entries(path, val));
+            }
+            Item::Array { ref val, .. } => {
+                let mut idx = 0;
+                for v in val {
+                    let mut path = path.to_vec();
+                    if v.is_value() {
+                        path.push(Segment::Index(idx));
+                        idx += 1;
+                    }
+                    children.push(Step::Enter(path, v));
+                }
+            }
+            Item::AoT(ref tables) => {
+                for (idx, table) in tables.iter().enumerate() {
+                    let mut path = path.to_vec();
+                    path.push(Segment::Index(idx));
+                    children.push(Step::Enter(path, table));
+                }
+            }
+            _ => {}
+        }
+        self.stack.extend(children.into_iter().rev());
+    }
+}
+
+impl<'b, 'a: 'b> Iterator for Walker<'b, 'a> {
+    type Item = (Vec<Segment<'b, 'a>>, &'b Item<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (path, item) = match self.stack.pop()? {
+                Step::Leave(path, item) => return Some((path, item)),
+                Step::Enter(path, item) => (path, item),
+            };
+            if item.is_trivia() && !self.trivia {
+                continue;
+            }
+            let pruned = match self.prune {
+                Some(ref mut prune) => prune(&path, item),
+                None => false,
+            };
+            if pruned {
+                return Some((path, item));
+            }
+            match self.order {
+                Order::Pre => {
+                    self.push_children(&path, item);
+                    return Some((path, item));
+                }
+                Order::Post => {
+                    self.stack.push(Step::Leave(path.clone(), item));
+                    self.push_children(&path, item);
+                }
+            }
+        }
+    }
+}
+
+impl<'b, 'a: 'b> fmt::Debug for Walker<'b, 'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Walker")
+            .field("order", &self.order)
+            .field("trivia", &self.trivia)
+            .finish()
+    }
+}
+
+/// Returns the steps for the entries of `container`, found at `path`.
+fn entries<'b, 'a: 'b>(
+    path: &[Segment<'b, 'a>],
+    container: &'b Container<'a>,
+) -> Vec<Step<'b, 'a>> {
+    container
+        .body
+        .iter()
+        .map(|(k, v)| {
+            let mut path = path.to_vec();
+            if let Some(k) = k {
+                path.push(Segment::Key(k));
+            }
+            Step::Enter(path, v)
+        })
+        .collect()
+}
+
+impl<'a> Container<'a> {
+    /// Returns a depth-first walker over every node below the container.
+    pub fn walk(&self) -> Walker<'_, 'a> {
+        Walker::new(entries(&[], self))
+    }
+}
+
+impl<'a> Item<'a> {
+    /// Returns a depth-first walker over the current item and every node
+    /// below it. The item itself has an empty path.
+    pub fn walk(&self) -> Walker<'_, 'a> {
+        Walker::new(vec![Step::Enter(Vec::new(), self)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parser::Parser;
+    use super::{Order, Segment};
+
+    /// Returns the path with keys split by `/` and indexes in brackets.
+    fn path_string(path: &[Segment]) -> String {
+        let mut s = String::new();
+        for segment in path {
+            match *segment {
+                Segment::Key(k) => {
+                    if !s.is_empty() {
+                        s.push('/');
+                    }
+                    s.push_str(&k.key);
+                }
+                Segment::Index(idx) => s.push_str(&format!("[{}]", idx)),
+            }
+        }
+        s
+    }
+
+    const SRC: &str = "# c\na = [{x = 1}, {b = 2}]\n[t]\nc = 3\n[[u]]\nd = 4\n";
+
+    #[test]
+    fn pre_order() {
+        let doc = Parser::new(SRC).parse().unwrap();
+        let paths: Vec<_> = doc.walk().map(|(path, _)| path_string(&path)).collect();
+        assert_eq!(
+            paths,
+            vec!["a", "a[0]", "a[0]/x", "a[1]", "a[1]/b", "t", "t/c", "u", "u[0]", "u[0]/d"]
+        );
+    }
+
+    #[test]
+    fn post_order_and_trivia() {
+        let doc = Parser::new(SRC).parse().unwrap();
+        let nodes: Vec<_> = doc.walk()
+            .order(Order::Post)
+            .trivia(true)
+            .filter(|&(_, item)| !item.is_ws())
+            .map(|(path, item)| (path_string(&path), item.is_trivia()))
+            .collect();
+        assert_eq!(nodes[0], ("".to_string(), true));
+        assert_eq!(nodes[1], ("a[0]/x".to_string(), false));
+        assert_eq!(nodes[5], ("a".to_string(), false));
+        assert_eq!(nodes.last().unwrap(), &("u".to_string(), false));
+    }
+
+    #[test]
+    fn prune() {
+        let doc = Parser::new(SRC).parse().unwrap();
+        let paths: Vec<_> = doc.walk()
+            .prune(|_, item| item.is_array() || item.is_aot())
+            .map(|(path, _)| path_string(&path))
+            .collect();
+        assert_eq!(paths, vec!["a", "t", "t/c", "u"]);
+
+        let paths: Vec<_> = doc["t"].walk().map(|(path, _)| path_string(&path)).collect();
+        assert_eq!(paths, vec!["", "c"]);
+    }
+
+    #[test]
+    fn nested_tables() {
+        let doc = Parser::new("[a]\n[a.b]\nc = 1\n").parse().unwrap();
+        let paths: Vec<_> = doc.walk().map(|(path, _)| path_string(&path)).collect();
+        assert_eq!(paths, vec!["a", "a/a.b", "a/a.b/c"]);
+    }
+}